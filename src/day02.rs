@@ -1,7 +1,17 @@
 use std::str::FromStr;
 
+use anyhow::bail;
+
 use crate::util::read_input;
 
+use self::{
+    bags::{Bag, Inference},
+    query::Query,
+};
+
+mod bags;
+mod query;
+
 pub fn answer2() {
     let input = read_input(2);
     let answer = solve2(&input);
@@ -40,6 +50,94 @@ fn solve1(input: &str, cubes: Vec<usize>) -> usize {
         .sum()
 }
 
+pub fn bags(args: &[String]) -> anyhow::Result<()> {
+    let input = read_input(2);
+    let games: Vec<Game> = input
+        .lines()
+        .filter_map(|l| Game::from_str(l).ok())
+        .collect();
+
+    let (mean_total, args) = match args {
+        [flag, mean, rest @ ..] if flag == "--mean" => (Some(mean.parse::<f64>()?), rest),
+        rest => (None, rest),
+    };
+
+    let candidate = match args {
+        [] => None,
+        [red, green, blue] => Some(Bag {
+            red: red.parse()?,
+            green: green.parse()?,
+            blue: blue.parse()?,
+        }),
+        _ => bail!("expected [--mean <cubes>] [<red> <green> <blue>]"),
+    };
+
+    let smallest = Bag::smallest(&games);
+    // without a guess, expect about as many cubes as the smallest bag that fits every game
+    let mean_total = mean_total.unwrap_or(smallest.total() as f64);
+    if !(mean_total > 0.0 && mean_total.is_finite()) {
+        bail!("--mean has to be a positive number of cubes, got {mean_total}");
+    }
+    let inference = Inference::new(&games, mean_total);
+
+    println!(
+        "day02 bags: prior total cubes ~ Poisson(mean {mean_total}), every split of a total equally likely"
+    );
+    println!("day02 bags: smallest bag that fits every game {smallest}");
+    let (red, green, blue) = inference.drawn_shares();
+    println!(
+        "day02 bags: cubes drawn {:.1}% red, {:.1}% green, {:.1}% blue",
+        red * 100.0,
+        green * 100.0,
+        blue * 100.0
+    );
+    if let Some(best) = inference.most_probable(1).first() {
+        println!(
+            "day02 bags: most probable {} (log-likelihood {:.3}), searched bags up to {} cubes",
+            best.bag,
+            best.log_likelihood,
+            inference.searched()
+        );
+    }
+
+    let (red, green, blue) = inference.posterior_mean();
+    println!("day02 bags: posterior mean {red:.2} red, {green:.2} green, {blue:.2} blue");
+
+    let mut report: Vec<Bag> = inference.most_probable(10).iter().map(|c| c.bag).collect();
+    report.extend(candidate);
+    for bag in report {
+        let consistent = inference.consistent_games(&bag);
+        if consistent.len() == games.len() {
+            println!(
+                "  {bag}: posterior {:.4}, consistent with all games",
+                inference.posterior(&bag)
+            );
+        } else {
+            println!(
+                "  {bag}: posterior {:.4}, consistent with {}/{} games {:?}",
+                inference.posterior(&bag),
+                consistent.len(),
+                games.len(),
+                consistent
+            );
+        }
+    }
+
+    Ok(())
+}
+
+pub fn query(args: &[String]) -> anyhow::Result<()> {
+    let query = Query::from_str(&args.join(" "))?;
+    let input = read_input(2);
+    let games: Vec<Game> = input
+        .lines()
+        .filter_map(|l| Game::from_str(l).ok())
+        .collect();
+
+    println!("day02 query: {}", query.run(&games)?);
+    Ok(())
+}
+
 #[derive(Debug)]
 struct Game {
     index: usize,
@@ -47,7 +145,7 @@ struct Game {
 }
 
 impl Game {
-    fn valid(&self, cubes: &Vec<usize>) -> bool {
+    fn valid(&self, cubes: &[usize]) -> bool {
         self.sets.iter().all(|set| set.check(cubes))
     }
 
@@ -98,7 +196,7 @@ struct Set {
 }
 
 impl Set {
-    fn check(&self, cubes: &Vec<usize>) -> bool {
+    fn check(&self, cubes: &[usize]) -> bool {
         cubes[0] >= self.red && cubes[1] >= self.green && cubes[2] >= self.blue
     }
 }
@@ -126,6 +224,18 @@ impl FromStr for Set {
     }
 }
 
+#[cfg(test)]
+fn example() -> Vec<Game> {
+    "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+    Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+    Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+    Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+    Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green"
+        .lines()
+        .filter_map(|l| Game::from_str(l.trim()).ok())
+        .collect()
+}
+
 #[test]
 fn test1() {
     let input = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
@@ -0,0 +1,356 @@
+use std::fmt;
+
+use super::{Game, Set};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bag {
+    pub red: usize,
+    pub green: usize,
+    pub blue: usize,
+}
+
+impl Bag {
+    // fewest cubes of each colour that fit every game
+    pub fn smallest(games: &[Game]) -> Self {
+        let sets = games.iter().flat_map(|game| game.sets.iter());
+        sets.fold(
+            Bag {
+                red: 0,
+                green: 0,
+                blue: 0,
+            },
+            |bag, set| Bag {
+                red: bag.red.max(set.red),
+                green: bag.green.max(set.green),
+                blue: bag.blue.max(set.blue),
+            },
+        )
+    }
+
+    pub fn total(&self) -> usize {
+        self.red + self.green + self.blue
+    }
+
+    fn holds(&self, set: &Set) -> bool {
+        set.red <= self.red && set.green <= self.green && set.blue <= self.blue
+    }
+
+    pub fn consistent_with(&self, game: &Game) -> bool {
+        game.sets.iter().all(|set| self.holds(set))
+    }
+}
+
+impl fmt::Display for Bag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} red, {} green, {} blue",
+            self.red, self.green, self.blue
+        )
+    }
+}
+
+// ln(n!), grown as bigger bags come up
+struct LnFactorials(Vec<f64>);
+
+impl LnFactorials {
+    fn new() -> Self {
+        LnFactorials(vec![0.0])
+    }
+
+    fn grow(&mut self, max: usize) {
+        for n in self.0.len()..=max {
+            self.0.push(self.0[n - 1] + (n as f64).ln());
+        }
+    }
+
+    fn ln_choose(&mut self, n: usize, k: usize) -> f64 {
+        if k > n {
+            return f64::NEG_INFINITY;
+        }
+        self.grow(n);
+        self.0[n] - self.0[k] - self.0[n - k]
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub bag: Bag,
+    pub log_likelihood: f64,
+    pub posterior: f64,
+}
+
+// log posterior this far below the best is left out, e^-40 is nothing
+const NEGLIGIBLE: f64 = 40.0;
+
+// multivariate hypergeometric per set, with a Poisson prior on the total
+pub struct Inference<'a> {
+    games: &'a [Game],
+    mean_total: f64,
+    // largest total the search looked at
+    searched: usize,
+    ln_factorials: LnFactorials,
+    candidates: Vec<Candidate>,
+}
+
+impl<'a> Inference<'a> {
+    pub fn new(games: &'a [Game], mean_total: f64) -> Self {
+        let mut inference = Inference {
+            games,
+            mean_total,
+            searched: 0,
+            ln_factorials: LnFactorials::new(),
+            candidates: vec![],
+        };
+        let sets: Vec<&Set> = games.iter().flat_map(|game| game.sets.iter()).collect();
+        let least = Bag::smallest(games);
+
+        // tabulated per colour count and per total rather than per bag
+        let mut red = vec![];
+        let mut green = vec![];
+        let mut blue = vec![];
+        let mut drawn = vec![];
+        let mut best = f64::NEG_INFINITY;
+        let mut scored: Vec<(Candidate, f64)> = vec![];
+        for total in 0.. {
+            let log_prior = inference.log_prior(total);
+            inference.searched = total;
+
+            let f = &mut inference.ln_factorials;
+            red.push(sets.iter().map(|s| f.ln_choose(total, s.red)).sum::<f64>());
+            green.push(
+                sets.iter()
+                    .map(|s| f.ln_choose(total, s.green))
+                    .sum::<f64>(),
+            );
+            blue.push(sets.iter().map(|s| f.ln_choose(total, s.blue)).sum::<f64>());
+            drawn.push(
+                sets.iter()
+                    .map(|s| f.ln_choose(total, s.red + s.green + s.blue))
+                    .sum::<f64>(),
+            );
+
+            if total < least.total() {
+                continue;
+            }
+            // totals peak at the mode, past it a negligible total means every bigger one is too
+            let mut total_best = f64::NEG_INFINITY;
+            for r in least.red..=total - least.green - least.blue {
+                for g in least.green..=total - r - least.blue {
+                    let bag = Bag {
+                        red: r,
+                        green: g,
+                        blue: total - r - g,
+                    };
+                    let log_likelihood =
+                        red[bag.red] + green[bag.green] + blue[bag.blue] - drawn[total];
+                    let log_posterior = log_likelihood + log_prior;
+                    total_best = total_best.max(log_posterior);
+                    if log_posterior < best - NEGLIGIBLE {
+                        continue;
+                    }
+                    best = best.max(log_posterior);
+                    scored.push((
+                        Candidate {
+                            bag,
+                            log_likelihood,
+                            posterior: 0.0,
+                        },
+                        log_posterior,
+                    ));
+                }
+            }
+            if total_best < best - NEGLIGIBLE {
+                break;
+            }
+        }
+
+        // log-sum-exp so a few hundred sets don't underflow to zero
+        scored.retain(|(_, log_posterior)| *log_posterior >= best - NEGLIGIBLE);
+        let evidence: f64 = scored.iter().map(|(_, lp)| (lp - best).exp()).sum();
+        inference.candidates = scored
+            .into_iter()
+            .map(|(candidate, log_posterior)| Candidate {
+                posterior: (log_posterior - best).exp() / evidence,
+                ..candidate
+            })
+            .collect();
+
+        inference
+    }
+
+    // Poisson on the total, shared evenly between its (total + 1)(total + 2) / 2 splits
+    fn log_prior(&mut self, total: usize) -> f64 {
+        let splits = ((total + 1) * (total + 2) / 2) as f64;
+        self.ln_factorials.grow(total);
+        total as f64 * self.mean_total.ln()
+            - self.mean_total
+            - self.ln_factorials.0[total]
+            - splits.ln()
+    }
+
+    // one set at a time, what the tables in `new` add up
+    #[cfg(test)]
+    fn set_log_likelihood(&mut self, bag: &Bag, set: &Set) -> f64 {
+        let drawn = set.red + set.green + set.blue;
+        self.ln_factorials.ln_choose(bag.red, set.red)
+            + self.ln_factorials.ln_choose(bag.green, set.green)
+            + self.ln_factorials.ln_choose(bag.blue, set.blue)
+            - self.ln_factorials.ln_choose(bag.total(), drawn)
+    }
+
+    #[cfg(test)]
+    fn log_likelihood(&mut self, bag: &Bag) -> f64 {
+        let mut sum = 0.0;
+        for game in self.games.iter() {
+            if !bag.consistent_with(game) {
+                return f64::NEG_INFINITY;
+            }
+            for set in game.sets.iter() {
+                sum += self.set_log_likelihood(bag, set);
+            }
+        }
+        sum
+    }
+
+    pub fn searched(&self) -> usize {
+        self.searched
+    }
+
+    // share of each colour over every cube drawn, where a big bag's mix tends
+    pub fn drawn_shares(&self) -> (f64, f64, f64) {
+        let sets = self.games.iter().flat_map(|game| game.sets.iter());
+        let (red, green, blue) = sets.fold((0, 0, 0), |acc, set| {
+            (acc.0 + set.red, acc.1 + set.green, acc.2 + set.blue)
+        });
+        let total = (red + green + blue) as f64;
+        (
+            red as f64 / total,
+            green as f64 / total,
+            blue as f64 / total,
+        )
+    }
+
+    pub fn consistent_games(&self, bag: &Bag) -> Vec<usize> {
+        self.games
+            .iter()
+            .filter(|game| bag.consistent_with(game))
+            .map(|game| game.index)
+            .collect()
+    }
+
+    pub fn most_probable(&self, n: usize) -> Vec<&Candidate> {
+        let mut candidates: Vec<_> = self.candidates.iter().collect();
+        candidates.sort_by(|a, b| b.posterior.total_cmp(&a.posterior));
+        candidates.truncate(n);
+        candidates
+    }
+
+    pub fn posterior(&self, bag: &Bag) -> f64 {
+        self.candidates
+            .iter()
+            .find(|c| c.bag == *bag)
+            .map_or(0.0, |c| c.posterior)
+    }
+
+    pub fn posterior_mean(&self) -> (f64, f64, f64) {
+        self.candidates.iter().fold((0.0, 0.0, 0.0), |acc, c| {
+            (
+                acc.0 + c.posterior * c.bag.red as f64,
+                acc.1 + c.posterior * c.bag.green as f64,
+                acc.2 + c.posterior * c.bag.blue as f64,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+use std::str::FromStr;
+
+#[test]
+fn test1() {
+    let games = super::example();
+    let smallest = Bag::smallest(&games);
+    assert_eq!(
+        Bag {
+            red: 20,
+            green: 13,
+            blue: 15
+        },
+        smallest
+    );
+    let mut inference = Inference::new(&games, smallest.total() as f64);
+
+    let part1 = Bag {
+        red: 12,
+        green: 13,
+        blue: 14,
+    };
+    assert_eq!(vec![1, 2, 5], inference.consistent_games(&part1));
+    assert_eq!(f64::NEG_INFINITY, inference.log_likelihood(&part1));
+
+    // a proper optimum, inside the search and beating every bag one cube away
+    let best = inference.most_probable(1)[0].clone();
+    assert!(best.bag.total() > smallest.total());
+    assert!(best.bag.total() < inference.searched());
+    let posterior = inference.posterior(&best.bag);
+    for (red, green, blue) in [(1, 0, 0), (0, 1, 0), (0, 0, 1)] {
+        let bigger = Bag {
+            red: best.bag.red + red,
+            green: best.bag.green + green,
+            blue: best.bag.blue + blue,
+        };
+        let smaller = Bag {
+            red: best.bag.red - red,
+            green: best.bag.green - green,
+            blue: best.bag.blue - blue,
+        };
+        assert!(inference.posterior(&bigger) < posterior);
+        assert!(inference.posterior(&smaller) < posterior);
+    }
+
+    // the tables in `new` agree with going set by set
+    let direct = inference.log_likelihood(&best.bag);
+    assert!((direct - best.log_likelihood).abs() < 1e-9);
+}
+
+#[test]
+fn test2() {
+    let games = super::example();
+    let inference = Inference::new(&games, 48.0);
+
+    let total: f64 = inference
+        .most_probable(usize::MAX)
+        .iter()
+        .map(|c| c.posterior)
+        .sum();
+    assert!((total - 1.0).abs() < 1e-9);
+
+    let (red, green, blue) = inference.posterior_mean();
+    assert!(red >= 20.0 && green >= 13.0 && blue >= 15.0);
+
+    // expecting more cubes moves the estimate up, but it settles each time
+    let bigger = Inference::new(&games, 100.0);
+    let (a, b) = (inference.most_probable(1)[0], bigger.most_probable(1)[0]);
+    assert!(a.bag.total() < b.bag.total());
+    assert!(b.bag.total() < bigger.searched());
+
+    let (red, green, blue) = inference.drawn_shares();
+    assert!((red + green + blue - 1.0).abs() < 1e-9);
+    assert!((red - 61.0 / 159.0).abs() < 1e-9);
+}
+
+#[test]
+fn test3() {
+    // a bag with exactly the drawn cubes forces every draw, nothing else comes close
+    let games = vec![Game::from_str("Game 1: 2 red, 1 blue; 2 red, 1 blue").unwrap()];
+    let mut inference = Inference::new(&games, 3.0);
+
+    let exact = Bag {
+        red: 2,
+        green: 0,
+        blue: 1,
+    };
+    assert_eq!(0.0, inference.log_likelihood(&exact));
+    assert_eq!(exact, inference.most_probable(1)[0].bag);
+}
@@ -0,0 +1,523 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, bail};
+
+use super::Game;
+
+// Grammar, loosest binding first:
+//
+//   query      := [aggregate] ["where" cond]
+//   aggregate  := "list" | "count" | ("sum" | "min" | "max") expr
+//   cond       := and ("or" and)*
+//   and        := unary ("and" unary)*
+//   unary      := "not" unary | "(" cond ")" | expr cmp expr | expr "in" INT (".." | "..=") INT
+//   expr       := term (("+" | "-") term)*
+//   term       := atom (("*" | "/") atom)*
+//   atom       := INT | "index" | "sets" | "power" | ("max" | "min" | "sum") "(" color ")" | "(" expr ")"
+//
+// e.g. `list where max(blue) > 10`, `sum sum(red)`, `sum power where index in 10..=20`
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Int(i64),
+    Ident(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Int(n) => write!(f, "{n}"),
+            Token::Ident(s) => write!(f, "{s}"),
+            Token::Symbol(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+// longest first so `..=` wins over `..` and `>=` over `>`
+const SYMBOLS: [&str; 14] = [
+    "..=", "..", ">=", "<=", "==", "!=", ">", "<", "(", ")", "+", "-", "*", "/",
+];
+
+fn tokenize(s: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut rest = s.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_ascii_digit() {
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            tokens.push(Token::Int(rest[..end].parse()?));
+            rest = &rest[end..];
+        } else if c.is_alphabetic() {
+            let end = rest
+                .find(|c: char| !c.is_alphanumeric())
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_lowercase()));
+            rest = &rest[end..];
+        } else if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else {
+            bail!("unexpected character '{c}' in query");
+        }
+        rest = rest.trim_start();
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Fold {
+    Max,
+    Min,
+    Sum,
+}
+
+#[derive(Debug, PartialEq)]
+enum Expr {
+    Int(i64),
+    Index,
+    Sets,
+    Power,
+    Color(Fold, Color),
+    Binary(Box<Expr>, &'static str, Box<Expr>),
+}
+
+#[derive(Debug, PartialEq)]
+enum Cond {
+    Compare(Expr, &'static str, Expr),
+    In(Expr, i64, i64),
+    Not(Box<Cond>),
+    And(Box<Cond>, Box<Cond>),
+    Or(Box<Cond>, Box<Cond>),
+}
+
+#[derive(Debug, PartialEq)]
+enum Aggregate {
+    List,
+    Count,
+    Fold(Fold, Expr),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Query {
+    aggregate: Aggregate,
+    filter: Option<Cond>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Answer {
+    Games(Vec<usize>),
+    Value(i64),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Games(games) => write!(f, "{} games {:?}", games.len(), games),
+            Answer::Value(n) => write!(f, "{n}"),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> anyhow::Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| anyhow!("unexpected end of query"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat_ident(&mut self, ident: &str) -> bool {
+        if let Some(Token::Ident(s)) = self.peek() {
+            if s == ident {
+                self.pos += 1;
+                return true;
+            }
+        }
+        false
+    }
+
+    fn eat_symbol(&mut self, symbols: &[&'static str]) -> Option<&'static str> {
+        if let Some(Token::Symbol(s)) = self.peek() {
+            if let Some(symbol) = symbols.iter().find(|symbol| *symbol == s) {
+                self.pos += 1;
+                return Some(symbol);
+            }
+        }
+        None
+    }
+
+    fn expect_symbol(&mut self, symbol: &'static str) -> anyhow::Result<()> {
+        match self.next()? {
+            Token::Symbol(s) if s == symbol => Ok(()),
+            token => bail!("expected '{symbol}' but found '{token}'"),
+        }
+    }
+
+    fn int(&mut self) -> anyhow::Result<i64> {
+        let negative = self.eat_symbol(&["-"]).is_some();
+        match self.next()? {
+            Token::Int(n) if negative => Ok(-n),
+            Token::Int(n) => Ok(n),
+            token => bail!("expected a number but found '{token}'"),
+        }
+    }
+
+    fn fold(&mut self) -> Option<Fold> {
+        if self.eat_ident("max") {
+            Some(Fold::Max)
+        } else if self.eat_ident("min") {
+            Some(Fold::Min)
+        } else if self.eat_ident("sum") {
+            Some(Fold::Sum)
+        } else {
+            None
+        }
+    }
+
+    // `sum(red)` is an expression, `sum sum(red)` and `sum power` are aggregates
+    fn at_fold_aggregate(&self) -> bool {
+        let is_fold = |token: Option<&Token>| matches!(token, Some(Token::Ident(s)) if matches!(s.as_str(), "max" | "min" | "sum"));
+        let is_color = matches!(
+            self.tokens.get(self.pos + 2),
+            Some(Token::Ident(s)) if matches!(s.as_str(), "red" | "green" | "blue")
+        );
+        let opens = self.tokens.get(self.pos + 1) == Some(&Token::Symbol("("));
+        is_fold(self.peek()) && !(opens && is_color)
+    }
+
+    fn query(&mut self) -> anyhow::Result<Query> {
+        let aggregate = if self.eat_ident("list") {
+            Aggregate::List
+        } else if self.eat_ident("count") {
+            Aggregate::Count
+        } else if self.at_fold_aggregate() {
+            let fold = self.fold().unwrap();
+            Aggregate::Fold(fold, self.expr()?)
+        } else {
+            Aggregate::List
+        };
+
+        let filter = if self.eat_ident("where") {
+            Some(self.cond()?)
+        } else {
+            None
+        };
+
+        if let Some(token) = self.peek() {
+            bail!("unexpected '{token}' at the end of the query");
+        }
+
+        Ok(Query { aggregate, filter })
+    }
+
+    fn cond(&mut self) -> anyhow::Result<Cond> {
+        let mut cond = self.and()?;
+        while self.eat_ident("or") {
+            cond = Cond::Or(Box::new(cond), Box::new(self.and()?));
+        }
+        Ok(cond)
+    }
+
+    fn and(&mut self) -> anyhow::Result<Cond> {
+        let mut cond = self.unary()?;
+        while self.eat_ident("and") {
+            cond = Cond::And(Box::new(cond), Box::new(self.unary()?));
+        }
+        Ok(cond)
+    }
+
+    fn unary(&mut self) -> anyhow::Result<Cond> {
+        if self.eat_ident("not") {
+            return Ok(Cond::Not(Box::new(self.unary()?)));
+        }
+
+        // a nested condition or a parenthesised expression, whichever parses
+        if self.peek() == Some(&Token::Symbol("(")) {
+            let start = self.pos;
+            self.pos += 1;
+            if let Ok(cond) = self.cond() {
+                if self.eat_symbol(&[")"]).is_some() && !self.continues_expr() {
+                    return Ok(cond);
+                }
+            }
+            self.pos = start;
+        }
+
+        let left = self.expr()?;
+        if self.eat_ident("in") {
+            let low = self.int()?;
+            let inclusive = match self.eat_symbol(&["..=", ".."]) {
+                Some("..=") => true,
+                Some(_) => false,
+                None => bail!("expected a range like 10..20 after 'in'"),
+            };
+            let high = self.int()?;
+            let high = if inclusive { high } else { high - 1 };
+            return Ok(Cond::In(left, low, high));
+        }
+
+        match self.eat_symbol(&[">=", "<=", "==", "!=", ">", "<"]) {
+            Some(op) => Ok(Cond::Compare(left, op, self.expr()?)),
+            None => match self.peek() {
+                Some(token) => bail!("expected a comparison but found '{token}'"),
+                None => bail!("expected a comparison at the end of the query"),
+            },
+        }
+    }
+
+    fn continues_expr(&self) -> bool {
+        matches!(
+            self.peek(),
+            Some(Token::Symbol(
+                ">=" | "<=" | "==" | "!=" | ">" | "<" | "+" | "-" | "*" | "/"
+            ))
+        ) || self.peek() == Some(&Token::Ident("in".to_string()))
+    }
+
+    fn expr(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.term()?;
+        while let Some(op) = self.eat_symbol(&["+", "-"]) {
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.term()?));
+        }
+        Ok(expr)
+    }
+
+    fn term(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.atom()?;
+        while let Some(op) = self.eat_symbol(&["*", "/"]) {
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.atom()?));
+        }
+        Ok(expr)
+    }
+
+    fn atom(&mut self) -> anyhow::Result<Expr> {
+        if let Some(fold) = self.fold() {
+            self.expect_symbol("(")?;
+            let color = match self.next()? {
+                Token::Ident(s) if s == "red" => Color::Red,
+                Token::Ident(s) if s == "green" => Color::Green,
+                Token::Ident(s) if s == "blue" => Color::Blue,
+                token => bail!("expected red, green or blue but found '{token}'"),
+            };
+            self.expect_symbol(")")?;
+            return Ok(Expr::Color(fold, color));
+        }
+
+        match self.next()? {
+            Token::Int(n) => Ok(Expr::Int(n)),
+            Token::Ident(s) if s == "index" => Ok(Expr::Index),
+            Token::Ident(s) if s == "sets" => Ok(Expr::Sets),
+            Token::Ident(s) if s == "power" => Ok(Expr::Power),
+            Token::Symbol("(") => {
+                let expr = self.expr()?;
+                self.expect_symbol(")")?;
+                Ok(expr)
+            }
+            token => bail!("unexpected '{token}' in expression"),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        parser.query()
+    }
+}
+
+impl Expr {
+    fn eval(&self, game: &Game) -> anyhow::Result<i64> {
+        let value = match self {
+            Expr::Int(n) => *n,
+            Expr::Index => game.index as i64,
+            Expr::Sets => game.sets.len() as i64,
+            Expr::Power => {
+                if game.sets.is_empty() {
+                    bail!("game {} has no sets to take the power of", game.index);
+                }
+                game.find_min_cubes()
+                    .into_iter()
+                    .try_fold(1i64, |acc, n| acc.checked_mul(i64::try_from(n).ok()?))
+                    .ok_or_else(|| anyhow!("overflow in game {}", game.index))?
+            }
+            Expr::Color(fold, color) => {
+                let counts = game.sets.iter().map(|set| match color {
+                    Color::Red => set.red as i64,
+                    Color::Green => set.green as i64,
+                    Color::Blue => set.blue as i64,
+                });
+                match fold {
+                    Fold::Max => counts.max().unwrap_or(0),
+                    Fold::Min => counts.min().unwrap_or(0),
+                    Fold::Sum => counts.sum(),
+                }
+            }
+            Expr::Binary(left, op, right) => {
+                let (left, right) = (left.eval(game)?, right.eval(game)?);
+                match *op {
+                    "+" => left.checked_add(right),
+                    "-" => left.checked_sub(right),
+                    "*" => left.checked_mul(right),
+                    "/" if right == 0 => bail!("division by zero in game {}", game.index),
+                    _ => left.checked_div(right),
+                }
+                .ok_or_else(|| anyhow!("overflow in game {}", game.index))?
+            }
+        };
+        Ok(value)
+    }
+}
+
+impl Cond {
+    fn eval(&self, game: &Game) -> anyhow::Result<bool> {
+        let value = match self {
+            Cond::Compare(left, op, right) => {
+                let (left, right) = (left.eval(game)?, right.eval(game)?);
+                match *op {
+                    ">=" => left >= right,
+                    "<=" => left <= right,
+                    "==" => left == right,
+                    "!=" => left != right,
+                    ">" => left > right,
+                    _ => left < right,
+                }
+            }
+            Cond::In(expr, low, high) => (*low..=*high).contains(&expr.eval(game)?),
+            Cond::Not(cond) => !cond.eval(game)?,
+            Cond::And(left, right) => left.eval(game)? && right.eval(game)?,
+            Cond::Or(left, right) => left.eval(game)? || right.eval(game)?,
+        };
+        Ok(value)
+    }
+}
+
+impl Query {
+    pub fn run(&self, games: &[Game]) -> anyhow::Result<Answer> {
+        let mut selected = vec![];
+        for game in games {
+            match &self.filter {
+                Some(cond) if !cond.eval(game)? => {}
+                _ => selected.push(game),
+            }
+        }
+
+        let answer = match &self.aggregate {
+            Aggregate::List => Answer::Games(selected.iter().map(|game| game.index).collect()),
+            Aggregate::Count => Answer::Value(selected.len() as i64),
+            Aggregate::Fold(fold, expr) => {
+                let values = selected
+                    .iter()
+                    .map(|game| expr.eval(game))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                let value = match fold {
+                    Fold::Max => values.into_iter().max().unwrap_or(0),
+                    Fold::Min => values.into_iter().min().unwrap_or(0),
+                    Fold::Sum => values
+                        .into_iter()
+                        .try_fold(0i64, |acc, n| acc.checked_add(n))
+                        .ok_or_else(|| anyhow!("overflow while summing"))?,
+                };
+                Answer::Value(value)
+            }
+        };
+
+        Ok(answer)
+    }
+}
+
+#[cfg(test)]
+fn run(query: &str) -> Answer {
+    Query::from_str(query)
+        .unwrap()
+        .run(&super::example())
+        .unwrap()
+}
+
+#[test]
+fn test1() {
+    assert_eq!(
+        Answer::Games(vec![3, 4]),
+        run("where max(blue) > 10 or max(red) > 12")
+    );
+    assert_eq!(Answer::Games(vec![4]), run("list where max(blue) > 10"));
+    assert_eq!(Answer::Value(5), run("count"));
+    assert_eq!(Answer::Value(4 + 1 + 1 + 25 + 23 + 7), run("sum sum(red)"));
+    assert_eq!(Answer::Value(2286), run("sum power"));
+    assert_eq!(
+        Answer::Value(1560 + 630),
+        run("sum power where index in 3..=4")
+    );
+    assert_eq!(Answer::Value(1560), run("sum power where index in 3..4"));
+}
+
+#[test]
+fn test2() {
+    // the part 1 answer: games possible with 12 red, 13 green and 14 blue
+    let query = "sum index where not (max(red) > 12 or max(green) > 13 or max(blue) > 14)";
+    assert_eq!(Answer::Value(8), run(query));
+
+    assert_eq!(
+        Answer::Games(vec![1, 2, 5]),
+        run("where (max(red) + 1) * 2 <= 14")
+    );
+    assert_eq!(
+        Answer::Value(20),
+        run("max max(red) where sets == 3 and min(green) >= 1")
+    );
+}
+
+#[test]
+fn test3() {
+    assert!(Query::from_str("where max(purple) > 1").is_err());
+    assert!(Query::from_str("where max(red) >").is_err());
+    assert!(Query::from_str("where index in 3").is_err());
+    assert!(Query::from_str("count where index > 1 extra").is_err());
+    assert!(Query::from_str("sum index / (index - index)")
+        .unwrap()
+        .run(&super::example())
+        .is_err());
+
+    // a game whose sets all failed to parse has nothing to take the power of
+    let games = vec![Game::from_str("Game 7: x red; y blue").unwrap()];
+    let error = Query::from_str("sum power")
+        .unwrap()
+        .run(&games)
+        .unwrap_err();
+    assert_eq!("game 7 has no sets to take the power of", error.to_string());
+
+    let games =
+        vec![Game::from_str("Game 8: 4000000000 red, 4000000000 green, 4000000000 blue").unwrap()];
+    let error = Query::from_str("sum power")
+        .unwrap()
+        .run(&games)
+        .unwrap_err();
+    assert_eq!("overflow in game 8", error.to_string());
+}
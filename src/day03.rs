@@ -1,7 +1,13 @@
 use std::str::FromStr;
 
+use anyhow::{anyhow, bail};
+
 use crate::util::read_input;
 
+use self::render::Format;
+
+mod render;
+
 #[derive(Debug)]
 enum Cell {
     Num(usize),
@@ -13,32 +19,131 @@ impl Cell {
     fn from_char(c: char) -> Self {
         match c {
             '.' => Self::Dot,
-            c if c.is_digit(10) => Cell::Num(c.to_digit(10).unwrap() as usize),
+            c if c.is_ascii_digit() => Cell::Num(c.to_digit(10).unwrap() as usize),
             _ => Self::Symbol(c),
         }
     }
 }
 
+// unbounded with `--features bigint`, num-bigint itself is always a dependency
+#[cfg(not(feature = "bigint"))]
+type Int = u64;
+#[cfg(feature = "bigint")]
+type Int = num_bigint::BigUint;
+
+#[cfg(not(feature = "bigint"))]
+fn checked_add(a: &Int, b: &Int) -> Option<Int> {
+    a.checked_add(*b)
+}
+
+#[cfg(not(feature = "bigint"))]
+fn checked_mul(a: &Int, b: &Int) -> Option<Int> {
+    a.checked_mul(*b)
+}
+
+#[cfg(feature = "bigint")]
+fn checked_add(a: &Int, b: &Int) -> Option<Int> {
+    Some(a + b)
+}
+
+#[cfg(feature = "bigint")]
+fn checked_mul(a: &Int, b: &Int) -> Option<Int> {
+    Some(a * b)
+}
+
+// how digit runs are read into numbers
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum Wrap {
+    // left to right, a number ends with its row
+    #[default]
+    Never,
+    // left to right, a number ending a row continues with digits starting the next row
+    Rows,
+    // top to bottom down each column
+    Vertical,
+}
+
+impl FromStr for Wrap {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "never" => Ok(Wrap::Never),
+            "rows" => Ok(Wrap::Rows),
+            "vertical" => Ok(Wrap::Vertical),
+            _ => bail!("unknown wrap mode {s}, expected never, rows or vertical"),
+        }
+    }
+}
+
+// a run of cells along a row or down a column, both ends inclusive
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Span {
+    start: (usize, usize),
+    end: (usize, usize),
+}
+
 #[derive(Debug)]
 struct Number {
-    number: String,
-    positions: Vec<(usize, usize)>,
+    value: Int,
+    spans: Vec<Span>,
     has_symbol_neighbour: bool,
 }
 
 impl Number {
-    fn push(&mut self, x: usize, y: usize, n: usize) {
-        self.number.push_str(&format!("{n}"));
-        self.positions.push((x, y));
+    fn new(position: (usize, usize), n: usize) -> Self {
+        Number {
+            value: Int::from(n as u32),
+            spans: vec![Span {
+                start: position,
+                end: position,
+            }],
+            has_symbol_neighbour: false,
+        }
+    }
+
+    fn push(&mut self, (x, y): (usize, usize), n: usize) -> anyhow::Result<()> {
+        let first = self.spans[0].start;
+        self.value = checked_mul(&self.value, &Int::from(10u32))
+            .and_then(|value| checked_add(&value, &Int::from(n as u32)))
+            .ok_or_else(|| {
+                anyhow!(
+                    "number starting at row {} column {} does not fit in 64 bits, \
+                     build with --features bigint",
+                    first.1 + 1,
+                    first.0 + 1
+                )
+            })?;
+
+        let last = self.spans.last_mut().unwrap();
+        let (end_x, end_y) = last.end;
+        let along_row = y == end_y && x == end_x + 1 && last.start.1 == end_y;
+        let down_column = x == end_x && y == end_y + 1 && last.start.0 == end_x;
+        if along_row || down_column {
+            last.end = (x, y);
+        } else {
+            self.spans.push(Span {
+                start: (x, y),
+                end: (x, y),
+            });
+        }
+        Ok(())
     }
 
-    fn number(&self) -> usize {
-        self.number.parse().unwrap()
+    fn positions(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.spans.iter().flat_map(|span| {
+            (span.start.1..=span.end.1)
+                .flat_map(move |y| (span.start.0..=span.end.0).map(move |x| (x, y)))
+        })
+    }
+
+    fn number(&self) -> &Int {
+        &self.value
     }
 
-    fn count(&self) -> Option<usize> {
+    fn count(&self) -> Option<&Int> {
         if self.has_symbol_neighbour {
-            Some(self.number.parse().unwrap())
+            Some(&self.value)
         } else {
             None
         }
@@ -51,62 +156,200 @@ struct Symbol {
     position: (usize, usize),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Neighbours {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+impl Neighbours {
+    fn matches(&self, count: usize) -> bool {
+        match self {
+            Neighbours::Exactly(n) => count == *n,
+            Neighbours::AtLeast(n) => count >= *n,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combine {
+    Product,
+    Sum,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct GearRule {
+    symbols: Vec<char>,
+    neighbours: Neighbours,
+    combine: Combine,
+}
+
+impl Default for GearRule {
+    // a `*` next to exactly two part numbers, worth their product
+    fn default() -> Self {
+        GearRule {
+            symbols: vec!['*'],
+            neighbours: Neighbours::Exactly(2),
+            combine: Combine::Product,
+        }
+    }
+}
+
+// `gears` and `render` options, in any order
+#[derive(Debug, Default)]
+struct Options {
+    rule: GearRule,
+    wrap: Wrap,
+    format: Format,
+}
+
+impl Options {
+    fn from_args(args: &[String]) -> anyhow::Result<Self> {
+        let mut options = Options::default();
+        let rule = &mut options.rule;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || match args.next() {
+                Some(value) => Ok(value),
+                None => bail!("{arg} needs a value"),
+            };
+            match arg.as_str() {
+                "--symbols" => rule.symbols = value()?.chars().collect(),
+                "--exactly" => rule.neighbours = Neighbours::Exactly(value()?.parse()?),
+                "--at-least" => rule.neighbours = Neighbours::AtLeast(value()?.parse()?),
+                "--product" => rule.combine = Combine::Product,
+                "--sum" => rule.combine = Combine::Sum,
+                "--wrap" => options.wrap = Wrap::from_str(value()?)?,
+                "--html" => options.format = Format::Html,
+                "--svg" => options.format = Format::Svg,
+                _ => bail!("unknown gear option {arg}"),
+            }
+        }
+        Ok(options)
+    }
+}
+
 #[derive(Debug)]
 struct Engine {
     schematic: Vec<Vec<Cell>>,
     numbers: Vec<Number>,
     symbols: Vec<Symbol>,
+    // index into `numbers` for every cell that holds a digit
+    number_ids: Vec<Vec<Option<usize>>>,
 }
 
 impl Engine {
-    fn gear_ratio(&self) -> usize {
-        let mut ratio = 0;
+    // ids of the numbers touching a cell, a number spanning several neighbours shows up repeatedly
+    fn neighbour_ids(&self, (x, y): (usize, usize)) -> impl Iterator<Item = usize> + '_ {
+        let rows = &self.number_ids[y.saturating_sub(1)..(y + 2).min(self.number_ids.len())];
+        rows.iter().flat_map(move |row| {
+            let cols = &row[x.saturating_sub(1).min(row.len())..(x + 2).min(row.len())];
+            cols.iter().flatten().copied()
+        })
+    }
+
+    fn adjacent_numbers(&self, position: (usize, usize)) -> Vec<usize> {
+        let mut ids: Vec<usize> = self.neighbour_ids(position).collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    // every symbol the rule accepts as a gear, with the numbers it joins
+    fn gears<'a>(
+        &'a self,
+        rule: &'a GearRule,
+    ) -> impl Iterator<Item = (&'a Symbol, Vec<usize>)> + 'a {
+        self.symbols
+            .iter()
+            .filter(|symbol| rule.symbols.contains(&symbol.character))
+            .map(|symbol| (symbol, self.adjacent_numbers(symbol.position)))
+            .filter(|(_, hits)| rule.neighbours.matches(hits.len()))
+    }
+
+    fn gear_ratio(&self, rule: &GearRule) -> anyhow::Result<Int> {
+        let mut ratio = Int::from(0u32);
+        for (gear, hits) in self.gears(rule) {
+            let mut numbers = hits.iter().map(|id| self.numbers[*id].number());
+            let value = match rule.combine {
+                Combine::Product => {
+                    numbers.try_fold(Int::from(1u32), |acc, n| checked_mul(&acc, n))
+                }
+                Combine::Sum => numbers.try_fold(Int::from(0u32), |acc, n| checked_add(&acc, n)),
+            };
+            ratio = value
+                .and_then(|value| checked_add(&ratio, &value))
+                .ok_or_else(|| {
+                    let (x, y) = gear.position;
+                    anyhow!(
+                        "gear ratio overflowed at row {} column {}, build with --features bigint",
+                        y + 1,
+                        x + 1
+                    )
+                })?;
+        }
+        Ok(ratio)
+    }
+
+    fn check_numbers_for_symbols(&mut self) {
+        let mut has_symbol_neighbour = vec![false; self.numbers.len()];
+        for (y, row) in self.schematic.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if let Cell::Symbol(_) = cell {
+                    for id in self.neighbour_ids((x, y)) {
+                        has_symbol_neighbour[id] = true;
+                    }
+                }
+            }
+        }
+
+        for (number, has_symbol_neighbour) in self.numbers.iter_mut().zip(has_symbol_neighbour) {
+            number.has_symbol_neighbour = has_symbol_neighbour;
+        }
+    }
+
+    // the scans the index replaced, for the tests and the bench
+    #[cfg(test)]
+    fn gear_ratio_scan(&self) -> Int {
+        let mut ratio = Int::from(0u32);
         for symbol in self.symbols.iter() {
             if symbol.character == '*' {
                 let mut hits = vec![];
                 let (x, y) = symbol.position;
                 for num in self.numbers.iter() {
-                    for (num_x, num_y) in num.positions.iter() {
-                        if x.abs_diff(*num_x) <= 1 && y.abs_diff(*num_y) <= 1 {
+                    for (num_x, num_y) in num.positions() {
+                        if x.abs_diff(num_x) <= 1 && y.abs_diff(num_y) <= 1 {
                             hits.push(num.number());
-                            /*
-                            println!(
-                                "Hit at: [{num_x}, {num_y}], added {}, hits={}",
-                                num.number(),
-                                hits.len()
-                            );
-                            */
                             break;
                         }
                     }
-                    if hits.len() == 2 {
-                        ratio += hits.iter().product::<usize>();
-                        break;
-                    }
+                }
+                if hits.len() == 2 {
+                    ratio += hits.into_iter().product::<Int>();
                 }
             }
         }
         ratio
     }
 
-    fn check_numbers_for_symbols(&mut self) {
+    #[cfg(test)]
+    fn check_numbers_for_symbols_scan(&mut self) {
         for number in self.numbers.iter_mut() {
-            for (x, y) in number.positions.iter() {
+            let positions: Vec<_> = number.positions().collect();
+            for (x, y) in positions {
                 let mut has_symbol_neighbour: bool = false;
-                let x = *x as i32;
-                let y = *y as i32;
+                let x = x as i32;
+                let y = y as i32;
                 for i in -1..2 {
                     for j in -1..2 {
                         let new_x = (x + i) as usize;
                         let new_y = (y + j) as usize;
 
-                        if let Some(row) = self.schematic.get(new_y) {
-                            if let Some(cell) = row.get(new_x) {
-                                if let Cell::Symbol(_) = cell {
-                                    has_symbol_neighbour = true;
-                                    break;
-                                }
-                            }
+                        if let Some(Cell::Symbol(_)) =
+                            self.schematic.get(new_y).and_then(|row| row.get(new_x))
+                        {
+                            has_symbol_neighbour = true;
+                            break;
                         }
                     }
                 }
@@ -118,69 +361,111 @@ impl Engine {
         }
     }
 
-    fn part_number_sum(&self) -> usize {
-        self.numbers.iter().filter_map(|n| n.count()).sum()
+    fn part_number_sum(&self) -> anyhow::Result<Int> {
+        self.numbers
+            .iter()
+            .filter_map(|n| n.count())
+            .try_fold(Int::from(0u32), |acc, n| checked_add(&acc, n))
+            .ok_or_else(|| anyhow!("part number sum overflowed, build with --features bigint"))
     }
 }
 
-impl FromStr for Engine {
-    type Err = anyhow::Error;
+// groups digits into numbers, cells in reading order
+fn read_numbers(schematic: &[Vec<Cell>], wrap: Wrap) -> anyhow::Result<Vec<Number>> {
+    let mut numbers = vec![];
+    let mut current: Option<Number> = None;
 
-    fn from_str(s: &str) -> anyhow::Result<Self> {
-        let mut numbers: Vec<Number> = vec![];
+    let mut visit = |cell: Option<&Cell>, position: (usize, usize)| -> anyhow::Result<()> {
+        match (cell, current.as_mut()) {
+            (Some(Cell::Num(n)), Some(number)) => number.push(position, *n)?,
+            (Some(Cell::Num(n)), None) => current = Some(Number::new(position, *n)),
+            _ => numbers.extend(current.take()),
+        }
+        Ok(())
+    };
+
+    match wrap {
+        Wrap::Never | Wrap::Rows => {
+            for (y, row) in schematic.iter().enumerate() {
+                if wrap == Wrap::Never || row.is_empty() {
+                    visit(None, (0, y))?;
+                }
+                for (x, cell) in row.iter().enumerate() {
+                    visit(Some(cell), (x, y))?;
+                }
+            }
+        }
+        Wrap::Vertical => {
+            let width = schematic.iter().map(|row| row.len()).max().unwrap_or(0);
+            for x in 0..width {
+                visit(None, (x, 0))?;
+                for (y, row) in schematic.iter().enumerate() {
+                    visit(row.get(x), (x, y))?;
+                }
+            }
+        }
+    }
+    visit(None, (0, 0))?;
+
+    Ok(numbers)
+}
+
+impl Engine {
+    fn parse(s: &str, wrap: Wrap) -> anyhow::Result<Self> {
         let mut schematic = vec![];
         let mut symbols = vec![];
 
-        let mut last_was_number = false;
         for (y, line) in s.lines().enumerate() {
             let mut cells = vec![];
             for (x, c) in line.trim().chars().enumerate() {
                 let cell = Cell::from_char(c);
-                match (&cell, last_was_number) {
-                    (Cell::Num(n), true) => {
-                        numbers.last_mut().unwrap().push(x, y, *n);
-                    }
-                    (Cell::Num(n), false) => {
-                        last_was_number = true;
-                        let mut number = Number {
-                            number: String::new(),
-                            positions: vec![],
-                            has_symbol_neighbour: false,
-                        };
-                        number.push(x, y, *n);
-                        numbers.push(number);
-                    }
-                    (Cell::Symbol(_), _) => {
-                        last_was_number = false;
-                        symbols.push(Symbol {
-                            character: c,
-                            position: (x, y),
-                        });
-                    }
-                    (_, _) => last_was_number = false,
+                if let Cell::Symbol(_) = cell {
+                    symbols.push(Symbol {
+                        character: c,
+                        position: (x, y),
+                    });
                 }
                 cells.push(cell);
             }
             schematic.push(cells);
         }
 
+        let numbers = read_numbers(&schematic, wrap)?;
+
+        let mut number_ids: Vec<Vec<Option<usize>>> =
+            schematic.iter().map(|row| vec![None; row.len()]).collect();
+        for (id, number) in numbers.iter().enumerate() {
+            for (x, y) in number.positions() {
+                number_ids[y][x] = Some(id);
+            }
+        }
+
         Ok(Engine {
             schematic,
             numbers,
             symbols,
+            number_ids,
         })
     }
 }
 
-fn solve1(input: &str) -> usize {
+impl FromStr for Engine {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Engine::parse(s, Wrap::default())
+    }
+}
+
+fn solve1(input: &str) -> Int {
     let mut engine = Engine::from_str(input).unwrap();
     engine.check_numbers_for_symbols();
-    engine.part_number_sum()
+    engine.part_number_sum().unwrap()
 }
 
-fn solve2(input: &str) -> usize {
+fn solve2(input: &str) -> Int {
     let engine = Engine::from_str(input).unwrap();
-    engine.gear_ratio()
+    engine.gear_ratio(&GearRule::default()).unwrap()
 }
 
 pub fn answer1() {
@@ -193,6 +478,44 @@ pub fn answer2() {
     println!("day03 part2: {}", solve2(input.trim()));
 }
 
+pub fn render(args: &[String]) -> anyhow::Result<()> {
+    let options = Options::from_args(args)?;
+
+    let input = read_input(3);
+    let mut engine = Engine::parse(input.trim(), options.wrap)?;
+    engine.check_numbers_for_symbols();
+    print!("{}", render::render(&engine, &options.rule, options.format));
+    Ok(())
+}
+
+pub fn gears(args: &[String]) -> anyhow::Result<()> {
+    let options = Options::from_args(args)?;
+    if options.format != Format::default() {
+        bail!("--html and --svg are for render, gears prints a number");
+    }
+    let input = read_input(3);
+    let engine = Engine::parse(input.trim(), options.wrap)?;
+    println!("day03 gears: {}", engine.gear_ratio(&options.rule)?);
+    Ok(())
+}
+
+#[cfg(test)]
+fn example() -> Engine {
+    let input = "467..114..
+    ...*......
+    ..35..633.
+    ......#...
+    617*......
+    .....+.58.
+    ..592.....
+    ......755.
+    ...$.*....
+    .664.598..";
+    let mut engine = Engine::from_str(input).unwrap();
+    engine.check_numbers_for_symbols();
+    engine
+}
+
 #[test]
 fn test1() {
     let input = "467..114..
@@ -205,7 +528,7 @@ fn test1() {
     ......755.
     ...$.*....
     .664.598..";
-    assert_eq!(4361, solve1(input));
+    assert_eq!(Int::from(4361u32), solve1(input));
 }
 
 #[test]
@@ -221,7 +544,7 @@ fn test2() {
     ...$.*....
     .664.598.."
         .trim();
-    assert_eq!(4361, solve1(input));
+    assert_eq!(Int::from(4361u32), solve1(input));
 }
 
 #[test]
@@ -241,7 +564,7 @@ fn test3() {
         1.1.......56
     ";
 
-    assert_eq!(solve1(&input), 413);
+    assert_eq!(solve1(input), Int::from(413u32));
 }
 
 #[test]
@@ -256,5 +579,240 @@ fn test4() {
     ......755.
     ...$.*....
     .664.598..";
-    assert_eq!(467835, solve2(input));
+    assert_eq!(Int::from(467835u32), solve2(input));
+}
+
+#[test]
+fn test5() {
+    // one gear with one, two and three neighbours each
+    let input = "
+        1.....2.3
+        *......*.
+        .........
+        .6.......
+        .*7......
+        8........
+    ";
+    let engine = Engine::from_str(input.trim()).unwrap();
+
+    assert_eq!(
+        Int::from(2 * 3u32),
+        engine.gear_ratio(&GearRule::default()).unwrap()
+    );
+
+    let at_least_two = GearRule {
+        neighbours: Neighbours::AtLeast(2),
+        ..GearRule::default()
+    };
+    assert_eq!(
+        Int::from(2 * 3 + 6 * 7 * 8u32),
+        engine.gear_ratio(&at_least_two).unwrap()
+    );
+
+    let single = GearRule {
+        neighbours: Neighbours::Exactly(1),
+        ..GearRule::default()
+    };
+    assert_eq!(Int::from(1u32), engine.gear_ratio(&single).unwrap());
+
+    let three_summed = GearRule {
+        neighbours: Neighbours::Exactly(3),
+        combine: Combine::Sum,
+        ..GearRule::default()
+    };
+    assert_eq!(
+        Int::from(6 + 7 + 8u32),
+        engine.gear_ratio(&three_summed).unwrap()
+    );
+}
+
+#[test]
+fn test6() {
+    // only the configured symbols count as gears
+    let input = "
+        2.3...4.5
+        .*.....#.
+    ";
+    let engine = Engine::from_str(input.trim()).unwrap();
+    assert_eq!(
+        Int::from(6u32),
+        engine.gear_ratio(&GearRule::default()).unwrap()
+    );
+
+    let args = ["--symbols", "#", "--sum"].map(String::from);
+    let rule = Options::from_args(&args).unwrap().rule;
+    assert_eq!(vec!['#'], rule.symbols);
+    assert_eq!(Int::from(9u32), engine.gear_ratio(&rule).unwrap());
+
+    assert!(Options::from_args(&["--exactly".to_string()]).is_err());
+    assert!(Options::from_args(&["--gears".to_string()]).is_err());
+}
+
+#[test]
+fn test7() {
+    let engine = Engine::from_str("..35..633.\n617*......").unwrap();
+    let spans: Vec<_> = engine.numbers.iter().map(|n| n.spans.clone()).collect();
+    let span = |start, end| vec![Span { start, end }];
+    assert_eq!(
+        vec![
+            span((2, 0), (3, 0)),
+            span((6, 0), (8, 0)),
+            span((0, 1), (2, 1))
+        ],
+        spans
+    );
+    assert_eq!(&Int::from(633u32), engine.numbers[1].number());
+
+    // 20 digits is past u64::MAX
+    let wide = "99999999999999999999*";
+    #[cfg(not(feature = "bigint"))]
+    assert!(Engine::from_str(wide).is_err());
+    #[cfg(feature = "bigint")]
+    assert_eq!(wide[..20].parse::<Int>().unwrap(), solve1(wide));
+}
+
+#[test]
+fn test8() {
+    let input = "
+        ..12
+        34..
+        5.6.
+        7.8*
+    ";
+    let values = |wrap| {
+        let mut engine = Engine::parse(input.trim(), wrap).unwrap();
+        engine.check_numbers_for_symbols();
+        let values: Vec<Int> = engine
+            .numbers
+            .iter()
+            .map(|n| n.number().to_owned())
+            .collect();
+        let sum = engine.part_number_sum().unwrap();
+        let ratio = engine.gear_ratio(&GearRule::default()).unwrap();
+        (values, sum, ratio, engine)
+    };
+    let ints = |ns: &[u32]| -> Vec<Int> { ns.iter().map(|n| Int::from(*n)).collect() };
+
+    let (numbers, sum, ratio, _) = values(Wrap::Never);
+    assert_eq!(ints(&[12, 34, 5, 6, 7, 8]), numbers);
+    assert_eq!(Int::from(6 + 8u32), sum);
+    assert_eq!(Int::from(6 * 8u32), ratio);
+
+    let (numbers, sum, ratio, engine) = values(Wrap::Rows);
+    assert_eq!(ints(&[1234, 5, 6, 7, 8]), numbers);
+    assert_eq!(Int::from(6 + 8u32), sum);
+    assert_eq!(Int::from(6 * 8u32), ratio);
+    let wrapped = vec![
+        Span {
+            start: (2, 0),
+            end: (3, 0),
+        },
+        Span {
+            start: (0, 1),
+            end: (1, 1),
+        },
+    ];
+    assert_eq!(wrapped, engine.numbers[0].spans);
+
+    let (numbers, sum, ratio, engine) = values(Wrap::Vertical);
+    assert_eq!(ints(&[357, 4, 1, 68, 2]), numbers);
+    assert_eq!(Int::from(68u32), sum);
+    assert_eq!(Int::from(0u32), ratio);
+    assert_eq!(
+        vec![(0, 1), (0, 2), (0, 3)],
+        engine.numbers[0].positions().collect::<Vec<_>>()
+    );
+
+    // --wrap goes anywhere among the gear options
+    for args in [
+        ["--wrap", "vertical", "--sum"],
+        ["--sum", "--wrap", "vertical"],
+    ] {
+        let options = Options::from_args(&args.map(String::from)).unwrap();
+        assert_eq!(Wrap::Vertical, options.wrap);
+        assert_eq!(Combine::Sum, options.rule.combine);
+    }
+    assert!(Options::from_args(&["--wrap".to_string(), "diagonal".to_string()]).is_err());
+    assert!(Options::from_args(&["--sum".to_string(), "--wrap".to_string()]).is_err());
+
+    let args = ["--wrap", "rows", "--svg", "--sum"].map(String::from);
+    assert_eq!(Format::Svg, Options::from_args(&args).unwrap().format);
+}
+
+#[cfg(test)]
+fn generate(width: usize, height: usize, seed: u64) -> String {
+    let mut next = crate::util::xorshift(seed);
+
+    let mut s = String::with_capacity((width + 1) * height);
+    for _ in 0..height {
+        for _ in 0..width {
+            let c = match next() % 10 {
+                0..=5 => '.',
+                6 | 7 => char::from(b'0' + (next() % 10) as u8),
+                8 => '*',
+                _ => ['#', '$', '+', '/', '@'][(next() % 5) as usize],
+            };
+            s.push(c);
+        }
+        s.push('\n');
+    }
+    s
+}
+
+#[test]
+fn test9() {
+    for seed in 1..20 {
+        let input = generate(40, 30, seed);
+        let mut engine = Engine::from_str(&input).unwrap();
+        engine.check_numbers_for_symbols();
+        let mut scanned = Engine::from_str(&input).unwrap();
+        scanned.check_numbers_for_symbols_scan();
+
+        assert_eq!(
+            scanned.part_number_sum().unwrap(),
+            engine.part_number_sum().unwrap()
+        );
+        assert_eq!(
+            scanned.gear_ratio_scan(),
+            engine.gear_ratio(&GearRule::default()).unwrap()
+        );
+    }
+}
+
+// cargo test --release bench -- --ignored --nocapture
+#[test]
+#[ignore]
+fn bench() {
+    use std::time::Instant;
+
+    // the gear scan is quadratic, so it only runs on the smaller schematics
+    for size in [100, 300, 1000, 3000] {
+        let input = generate(size, size, 42);
+        let engine = Engine::from_str(&input).unwrap();
+
+        let start = Instant::now();
+        let indexed = engine.gear_ratio(&GearRule::default()).unwrap();
+        let index_time = start.elapsed();
+
+        if size <= 300 {
+            let start = Instant::now();
+            let scanned = engine.gear_ratio_scan();
+            let scan_time = start.elapsed();
+
+            assert_eq!(scanned, indexed);
+            println!("{size}x{size} gear_ratio: scan {scan_time:?}, index {index_time:?}");
+        } else {
+            println!("{size}x{size} gear_ratio: index {index_time:?}");
+        }
+
+        let mut engine = Engine::from_str(&input).unwrap();
+        let start = Instant::now();
+        engine.check_numbers_for_symbols_scan();
+        let scan_time = start.elapsed();
+
+        let start = Instant::now();
+        engine.check_numbers_for_symbols();
+        let index_time = start.elapsed();
+        println!("{size}x{size} part numbers: scan {scan_time:?}, index {index_time:?}");
+    }
 }
@@ -0,0 +1,233 @@
+use std::fmt::Write;
+
+use super::{Cell, Engine, GearRule};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Format {
+    #[default]
+    Ansi,
+    Html,
+    Svg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Class {
+    PartNumber,
+    Number,
+    Gear,
+    Symbol,
+    Dot,
+}
+
+impl Class {
+    fn ansi(&self) -> &'static str {
+        match self {
+            Class::PartNumber => "\x1b[32m",
+            Class::Number => "\x1b[31m",
+            Class::Gear => "\x1b[1;33m",
+            Class::Symbol => "\x1b[36m",
+            Class::Dot => "\x1b[2m",
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Class::PartNumber => "part",
+            Class::Number => "number",
+            Class::Gear => "gear",
+            Class::Symbol => "symbol",
+            Class::Dot => "dot",
+        }
+    }
+
+    fn color(&self) -> &'static str {
+        match self {
+            Class::PartNumber => "#2e9e44",
+            Class::Number => "#c93c3c",
+            Class::Gear => "#d4a017",
+            Class::Symbol => "#2a8fbd",
+            Class::Dot => "#b0b0b0",
+        }
+    }
+}
+
+const CLASSES: [Class; 5] = [
+    Class::PartNumber,
+    Class::Number,
+    Class::Gear,
+    Class::Symbol,
+    Class::Dot,
+];
+
+// needs `check_numbers_for_symbols` first, or every number shows as a non-part
+fn classify(engine: &Engine, rule: &GearRule) -> Vec<Vec<(char, Class)>> {
+    let mut rows: Vec<Vec<(char, Class)>> = engine
+        .schematic
+        .iter()
+        .zip(engine.number_ids.iter())
+        .map(|(cells, ids)| {
+            cells
+                .iter()
+                .zip(ids.iter())
+                .map(|(cell, id)| match (cell, id) {
+                    (Cell::Num(n), Some(id)) => {
+                        let c = char::from_digit(*n as u32, 10).unwrap();
+                        if engine.numbers[*id].has_symbol_neighbour {
+                            (c, Class::PartNumber)
+                        } else {
+                            (c, Class::Number)
+                        }
+                    }
+                    (Cell::Symbol(c), _) => (*c, Class::Symbol),
+                    _ => ('.', Class::Dot),
+                })
+                .collect()
+        })
+        .collect();
+
+    for (gear, _) in engine.gears(rule) {
+        let (x, y) = gear.position;
+        rows[y][x].1 = Class::Gear;
+    }
+
+    rows
+}
+
+fn escape(c: char) -> String {
+    match c {
+        '&' => "&amp;".to_string(),
+        '<' => "&lt;".to_string(),
+        '>' => "&gt;".to_string(),
+        '"' => "&quot;".to_string(),
+        c => c.to_string(),
+    }
+}
+
+// consecutive cells of the same class, so a number comes out as one coloured run
+fn runs(row: &[(char, Class)]) -> Vec<(String, Class)> {
+    let mut runs: Vec<(String, Class)> = vec![];
+    for (c, class) in row.iter() {
+        match runs.last_mut() {
+            Some((run, last)) if last == class => run.push(*c),
+            _ => runs.push((c.to_string(), *class)),
+        }
+    }
+    runs
+}
+
+fn ansi(rows: &[Vec<(char, Class)>]) -> String {
+    let mut s = String::new();
+    for row in rows {
+        for (run, class) in runs(row) {
+            let _ = write!(s, "{}{run}\x1b[0m", class.ansi());
+        }
+        s.push('\n');
+    }
+    s
+}
+
+fn html(rows: &[Vec<(char, Class)>]) -> String {
+    let mut s = String::from("<!DOCTYPE html>\n<html>\n<head>\n<style>\n");
+    s.push_str("pre { font-family: monospace; line-height: 1.2; }\n");
+    for class in CLASSES {
+        let _ = writeln!(s, ".{} {{ color: {}; }}", class.name(), class.color());
+    }
+    s.push_str(".gear { font-weight: bold; }\n</style>\n</head>\n<body>\n<pre>\n");
+
+    for row in rows {
+        for (run, class) in runs(row) {
+            let run: String = run.chars().map(escape).collect();
+            let _ = write!(s, "<span class=\"{}\">{run}</span>", class.name());
+        }
+        s.push('\n');
+    }
+
+    s.push_str("</pre>\n</body>\n</html>\n");
+    s
+}
+
+fn svg(rows: &[Vec<(char, Class)>]) -> String {
+    const CELL_WIDTH: usize = 10;
+    const CELL_HEIGHT: usize = 16;
+
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0) * CELL_WIDTH;
+    let height = rows.len() * CELL_HEIGHT;
+
+    let mut s = String::new();
+    let _ = writeln!(
+        s,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         font-family=\"monospace\" font-size=\"14\">"
+    );
+    for (y, row) in rows.iter().enumerate() {
+        for (x, (c, class)) in row.iter().enumerate() {
+            if *class == Class::Dot {
+                continue;
+            }
+            if *class == Class::Gear {
+                let _ = writeln!(
+                    s,
+                    "<rect class=\"gear\" x=\"{}\" y=\"{}\" width=\"{CELL_WIDTH}\" height=\"{CELL_HEIGHT}\" \
+                     fill=\"{}\" fill-opacity=\"0.3\"/>",
+                    x * CELL_WIDTH,
+                    y * CELL_HEIGHT,
+                    class.color()
+                );
+            }
+            let _ = writeln!(
+                s,
+                "<text class=\"{}\" x=\"{}\" y=\"{}\" fill=\"{}\">{}</text>",
+                class.name(),
+                x * CELL_WIDTH,
+                (y + 1) * CELL_HEIGHT - 4,
+                class.color(),
+                escape(*c)
+            );
+        }
+    }
+    s.push_str("</svg>\n");
+    s
+}
+
+pub fn render(engine: &Engine, rule: &GearRule, format: Format) -> String {
+    let rows = classify(engine, rule);
+    match format {
+        Format::Ansi => ansi(&rows),
+        Format::Html => html(&rows),
+        Format::Svg => svg(&rows),
+    }
+}
+
+#[test]
+fn test1() {
+    let engine = super::example();
+    let rows = classify(&engine, &GearRule::default());
+
+    assert_eq!(('4', Class::PartNumber), rows[0][0]);
+    assert_eq!(('1', Class::Number), rows[0][5]);
+    assert_eq!(('*', Class::Gear), rows[1][3]);
+    // touches only 617, so it's a plain symbol
+    assert_eq!(('*', Class::Symbol), rows[4][3]);
+    assert_eq!(('#', Class::Symbol), rows[3][6]);
+    assert_eq!(('.', Class::Dot), rows[0][3]);
+
+    let ansi = render(&engine, &GearRule::default(), Format::Ansi);
+    assert!(ansi.starts_with("\x1b[32m467\x1b[0m"));
+    assert_eq!(10, ansi.lines().count());
+}
+
+#[test]
+fn test2() {
+    let engine = super::example();
+
+    let html = render(&engine, &GearRule::default(), Format::Html);
+    assert!(html.contains("<span class=\"number\">114</span>"));
+    assert!(html.contains("<span class=\"gear\">*</span>"));
+    assert_eq!(2, html.matches("class=\"gear\"").count());
+
+    let svg = render(&engine, &GearRule::default(), Format::Svg);
+    assert!(svg.starts_with("<svg "));
+    assert_eq!(2, svg.matches("<rect class=\"gear\"").count());
+    // one text element per digit of 114 and 58
+    assert_eq!(5, svg.matches("class=\"number\"").count());
+}
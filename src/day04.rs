@@ -1,33 +1,156 @@
-use std::str::FromStr;
+use std::{collections::BTreeSet, fmt, ops::RangeInclusive, str::FromStr};
+
+use anyhow::{anyhow, bail};
+use num_bigint::BigUint;
 
 use crate::util::read_input;
 
+use self::cascade::Cascade;
+
+mod cascade;
+
+// below this in the bitset
+const SMALL: usize = 1 << 12;
+
+// card numbers are small, the odd big one goes in `large`
+#[derive(Debug, Clone, Default, PartialEq)]
+struct NumberSet {
+    small: Vec<u64>,
+    large: BTreeSet<usize>,
+}
+
+impl NumberSet {
+    // false if `n` was already in the set
+    fn insert(&mut self, n: usize) -> bool {
+        if n >= SMALL {
+            return self.large.insert(n);
+        }
+        let (word, bit) = (n / 64, n % 64);
+        if word >= self.small.len() {
+            self.small.resize(word + 1, 0);
+        }
+        let new = self.small[word] & (1 << bit) == 0;
+        self.small[word] |= 1 << bit;
+        new
+    }
+
+    fn intersection_len(&self, other: &NumberSet) -> usize {
+        let small: usize = self
+            .small
+            .iter()
+            .zip(other.small.iter())
+            .map(|(a, b)| (a & b).count_ones() as usize)
+            .sum();
+        small + self.large.intersection(&other.large).count()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Scoring {
+    // 1 point for the first match, doubled for every further one
+    Doubling,
+    // 1 point per match
+    Linear,
+    // points for 0, 1, 2, ... matches, the last entry covers everything above
+    Table(Vec<usize>),
+}
+
+impl Scoring {
+    fn score(&self, matching: usize) -> anyhow::Result<usize> {
+        match self {
+            Scoring::Doubling if matching == 0 => Ok(0),
+            Scoring::Doubling => u32::try_from(matching - 1)
+                .ok()
+                .and_then(|shift| 1usize.checked_shl(shift))
+                .ok_or_else(|| anyhow!("{matching} matches is too many points to double")),
+            Scoring::Linear => Ok(matching),
+            Scoring::Table(table) => Ok(table.get(matching).or(table.last()).copied().unwrap_or(0)),
+        }
+    }
+}
+
+impl FromStr for Scoring {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "doubling" => Ok(Scoring::Doubling),
+            "linear" => Ok(Scoring::Linear),
+            table => {
+                let table = table
+                    .split(',')
+                    .map(|n| n.trim().parse())
+                    .collect::<Result<Vec<usize>, _>>();
+                match table {
+                    Ok(table) => Ok(Scoring::Table(table)),
+                    Err(_) => bail!("expected doubling, linear or a table like 0,1,3,6, got {s}"),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Problem {
+    DuplicateWinning(usize),
+    DuplicateOwned(usize),
+    OutOfRange(usize),
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::DuplicateWinning(n) => write!(f, "{n} is listed twice as a winning number"),
+            Problem::DuplicateOwned(n) => write!(f, "{n} is listed twice as an owned number"),
+            Problem::OutOfRange(n) => write!(f, "{n} is out of range"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct Card {
     index: usize,
     winning: Vec<usize>,
     owned: Vec<usize>,
+    winning_set: NumberSet,
+    owned_set: NumberSet,
 }
 
 impl Card {
+    // distinct owned numbers that are winning, duplicates count once
     fn matching(&self) -> usize {
-        self.owned
-            .iter()
-            .map(|n| if self.winning.contains(n) { 1 } else { 0 })
-            .sum()
+        self.winning_set.intersection_len(&self.owned_set)
     }
 
-    fn worth(&self) -> usize {
-        self.owned.iter().fold(0, |acc, n| {
-            let contained = self.winning.contains(n);
-            if contained && acc == 0 {
-                acc + 1
-            } else if contained && acc > 0 {
-                acc * 2
-            } else {
-                acc
+    fn worth(&self, scoring: &Scoring) -> anyhow::Result<usize> {
+        scoring
+            .score(self.matching())
+            .map_err(|e| anyhow!("card {}: {e}", self.index))
+    }
+
+    fn validate(&self, range: &RangeInclusive<usize>) -> Vec<Problem> {
+        let mut problems = vec![];
+
+        let mut seen = NumberSet::default();
+        for n in self.winning.iter() {
+            if !seen.insert(*n) {
+                problems.push(Problem::DuplicateWinning(*n));
             }
-        })
+        }
+        let mut seen = NumberSet::default();
+        for n in self.owned.iter() {
+            if !seen.insert(*n) {
+                problems.push(Problem::DuplicateOwned(*n));
+            }
+        }
+
+        for n in self.winning.iter().chain(self.owned.iter()) {
+            if !range.contains(n) {
+                problems.push(Problem::OutOfRange(*n));
+            }
+        }
+
+        problems
     }
 }
 
@@ -40,37 +163,92 @@ impl FromStr for Card {
         let index = index.trim().parse().unwrap();
         let (winning, owned) = lists.split_once("|").unwrap();
 
-        let winning = winning
+        let winning: Vec<usize> = winning
             .split_whitespace()
             .map(|s| s.parse().unwrap())
             .collect();
 
-        let owned = owned
+        let owned: Vec<usize> = owned
             .split_whitespace()
             .map(|s| s.parse().unwrap())
             .collect();
 
+        let mut winning_set = NumberSet::default();
+        winning.iter().for_each(|n| {
+            winning_set.insert(*n);
+        });
+        let mut owned_set = NumberSet::default();
+        owned.iter().for_each(|n| {
+            owned_set.insert(*n);
+        });
+
         Ok(Card {
             index,
             winning,
             owned,
+            winning_set,
+            owned_set,
         })
     }
 }
 
 fn solve1(input: &str) -> usize {
-    let cards: Vec<_> = input
+    total(&cards(input), &Scoring::Doubling).unwrap()
+}
+
+fn total(cards: &[Card], scoring: &Scoring) -> anyhow::Result<usize> {
+    cards.iter().try_fold(0usize, |sum, card| {
+        sum.checked_add(card.worth(scoring)?)
+            .ok_or_else(|| anyhow!("the score overflowed at card {}", card.index))
+    })
+}
+
+fn cards(input: &str) -> Vec<Card> {
+    input
         .lines()
         .map(|line| Card::from_str(line.trim()).unwrap())
-        .collect();
+        .collect()
+}
+
+pub fn score(args: &[String]) -> anyhow::Result<()> {
+    let scoring = match args {
+        [] => Scoring::Doubling,
+        [scoring] => Scoring::from_str(scoring)?,
+        _ => bail!("expected doubling, linear or a table like 0,1,3,6"),
+    };
+    let input = read_input(4);
+    println!("day04 score: {}", total(&cards(&input), &scoring)?);
+    Ok(())
+}
 
-    /*
-    for card in &cards {
-        println!("{:?} {}", card, card.worth());
+pub fn validate(args: &[String]) -> anyhow::Result<()> {
+    let range = match args {
+        [] => 1..=99,
+        [low, high] => low.parse()?..=high.parse()?,
+        _ => bail!("expected no range or <low> <high>"),
+    };
+    let input = read_input(4);
+
+    let mut count = 0;
+    for card in cards(&input) {
+        for problem in card.validate(&range) {
+            println!("Card {}: {problem}", card.index);
+            count += 1;
+        }
     }
-    */
+    println!("day04 validate: {count} problems");
+    Ok(())
+}
 
-    cards.iter().map(|c| c.worth()).sum()
+pub fn cascade(args: &[String]) -> anyhow::Result<()> {
+    let input = read_input(4);
+    let cascade = Cascade::new(&cards(&input));
+    match args {
+        [] => print!("{cascade}"),
+        [dot] if dot == "--dot" => print!("{}", cascade.dot()),
+        _ => bail!("expected no arguments or --dot"),
+    }
+    Ok(())
 }
 
 pub fn answer1() {
@@ -83,32 +261,39 @@ pub fn answer2() {
     println!("day04 part2: {}", solve2(&input));
 }
 
-fn solve2(input: &str) -> usize {
-    let cards: Vec<_> = input
-        .lines()
-        .map(|line| Card::from_str(line.trim()).unwrap())
-        .collect();
+fn solve2(input: &str) -> BigUint {
+    Cascade::new(&cards(input)).total()
+}
+
+// copy by copy, as the puzzle tells it
+#[cfg(test)]
+fn solve2_looped(input: &str) -> usize {
+    let cards = cards(input);
 
     let mut amount_cards: Vec<usize> = (0..cards.len()).map(|_| 1).collect();
 
     for (i, card) in cards.iter().enumerate() {
         let matching = card.matching();
-        //println!("Card {} has {matching} matching numbers.", card.index);
-
         for _ in 0..amount_cards[i] {
             for n in 0..matching {
-                if let Some(num) = amount_cards.get_mut(card.index + n) {
-                    //println!("Won copy of card {}", card.index + n + 1);
+                if let Some(num) = amount_cards.get_mut(i + 1 + n) {
                     *num += 1;
                 }
             }
         }
-        //println!("{:?}", amount_cards);
     }
 
     amount_cards.iter().sum()
 }
 
+#[cfg(test)]
+const EXAMPLE: &str = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+    Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+    Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+    Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+    Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+    Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
+
 #[test]
 fn test2() {
     let input = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
@@ -118,7 +303,7 @@ fn test2() {
     Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
     Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
-    assert_eq!(30, solve2(input));
+    assert_eq!(BigUint::from(30u32), solve2(input));
 }
 
 #[test]
@@ -130,11 +315,129 @@ fn test1() {
     Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
     Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
-    let cards: Vec<_> = input
-        .lines()
-        .map(|line| Card::from_str(line.trim()).unwrap())
-        .collect();
+    assert_eq!(13, total(&cards(input), &Scoring::Doubling).unwrap());
+}
+
+#[test]
+fn test5() {
+    let cards = cards(EXAMPLE);
+
+    let total = |scoring: &Scoring| total(&cards, scoring).unwrap();
+    assert_eq!(4 + 2 + 2 + 1, total(&Scoring::Linear));
+    assert_eq!(
+        6 + 1 + 1,
+        total(&Scoring::from_str("0, 0, 1, 3, 6").unwrap())
+    );
+    // four matches is past the end of the table, so it takes the last entry
+    assert_eq!(5 + 3 + 3 + 1, total(&Scoring::from_str("0,1,3,5").unwrap()));
+    assert!(Scoring::from_str("tripling").is_err());
+}
+
+#[test]
+fn test6() {
+    // 5 and 48 are duplicated, 48 matches once however often it shows up
+    let card = Card::from_str("Card 1: 41 48 48 0 | 48 5 5 41 120").unwrap();
+    assert_eq!(2, card.matching());
+    assert_eq!(2, card.worth(&Scoring::Doubling).unwrap());
+
+    let problems = card.validate(&(1..=99));
+    assert_eq!(
+        vec![
+            Problem::DuplicateWinning(48),
+            Problem::DuplicateOwned(5),
+            Problem::OutOfRange(0),
+            Problem::OutOfRange(120),
+        ],
+        problems
+    );
+    assert_eq!(
+        "48 is listed twice as a winning number",
+        problems[0].to_string()
+    );
+}
+
+// `cards` cards where card i has `matching(i)` winning numbers among its own
+#[cfg(test)]
+fn generate(cards: usize, matching: impl Fn(usize) -> usize) -> String {
+    (1..=cards)
+        .map(|i| {
+            let m = matching(i);
+            let winning: Vec<String> = (1..=10).map(|n| n.to_string()).collect();
+            let owned: Vec<String> = (1..=10)
+                .map(|n| (if n <= m { n } else { n + 10 }).to_string())
+                .collect();
+            format!("Card {i}: {} | {}\n", winning.join(" "), owned.join(" "))
+        })
+        .collect()
+}
+
+#[test]
+fn test3() {
+    for cards in 1..30 {
+        let input = generate(cards, |i| (i * 7) % 5);
+        assert_eq!(BigUint::from(solve2_looped(&input)), solve2(&input));
+    }
+}
+
+#[test]
+fn test4() {
+    // every card wins copies of the next ten, so copies roughly double per card
+    let input = generate(200, |_| 10);
+
+    // card j ends up with 1 + the copies of the ten cards before it
+    let mut expected: Vec<BigUint> = vec![];
+    for j in 0..200usize {
+        let won: BigUint = expected[j.saturating_sub(10)..j].iter().sum();
+        expected.push(won + 1u32);
+    }
+    let expected: BigUint = expected.iter().sum();
+
+    let total = solve2(&input);
+    assert_eq!(expected, total);
+    assert!(total > BigUint::from(u64::MAX));
+}
+
+#[test]
+fn test7() {
+    // a huge number stays out of the bitset, so validation gets to report it
+    let card = Card::from_str("Card 1: 41 48 | 48 100000000000000 100000000000000").unwrap();
+    assert_eq!(1, card.matching());
+    assert_eq!(
+        vec![
+            Problem::DuplicateOwned(100000000000000),
+            Problem::OutOfRange(100000000000000),
+            Problem::OutOfRange(100000000000000),
+        ],
+        card.validate(&(1..=99))
+    );
+    let card = Card::from_str("Card 2: 5000 100000000000000 7 | 100000000000000 7 5000").unwrap();
+    assert_eq!(3, card.matching());
+
+    // 64 matches still fit in a usize, 65 don't
+    let numbers: Vec<String> = (1..=65).map(|n| n.to_string()).collect();
+    let card = |n: usize| {
+        let numbers = numbers[..n].join(" ");
+        Card::from_str(&format!("Card 3: {numbers} | {numbers}")).unwrap()
+    };
+    assert_eq!(1 << 63, card(64).worth(&Scoring::Doubling).unwrap());
+    let error = card(65).worth(&Scoring::Doubling).unwrap_err();
+    assert_eq!(
+        "card 3: 65 matches is too many points to double",
+        error.to_string()
+    );
+    assert!(total(&[card(64), card(64)], &Scoring::Doubling).is_err());
+}
+
+#[test]
+fn test8() {
+    // copies go to the cards that follow, whatever they are numbered
+    let input = "Card 10: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+    Card 11: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+    Card 20:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+    Card 3: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+    Card 31: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+    Card 7: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
-    let sum: usize = cards.iter().map(|c| c.worth()).sum();
-    assert_eq!(13, sum);
+    assert_eq!(BigUint::from(30u32), solve2(input));
+    assert_eq!(30, solve2_looped(input));
 }
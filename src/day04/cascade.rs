@@ -0,0 +1,143 @@
+use std::fmt::{self, Write};
+
+use num_bigint::BigUint;
+
+use super::Card;
+
+#[derive(Debug, Clone)]
+pub struct Row {
+    pub card: usize,
+    pub matching: usize,
+    pub copies: BigUint,
+    // (earlier row, copies of this card it handed out)
+    pub sources: Vec<(usize, BigUint)>,
+}
+
+// copies of every card and which earlier rows won them
+#[derive(Debug, Clone)]
+pub struct Cascade {
+    pub rows: Vec<Row>,
+}
+
+impl Cascade {
+    pub fn new(cards: &[Card]) -> Self {
+        let mut rows: Vec<Row> = cards
+            .iter()
+            .map(|card| Row {
+                card: card.index,
+                matching: card.matching(),
+                copies: BigUint::from(1u32),
+                sources: vec![],
+            })
+            .collect();
+
+        for i in 0..rows.len() {
+            let copies = rows[i].copies.clone();
+            let won = (i + 1).min(rows.len())..(i + 1 + rows[i].matching).min(rows.len());
+            for row in rows[won].iter_mut() {
+                row.copies += &copies;
+                row.sources.push((i, copies.clone()));
+            }
+        }
+
+        Cascade { rows }
+    }
+
+    pub fn total(&self) -> BigUint {
+        self.rows.iter().map(|row| &row.copies).sum()
+    }
+
+    pub fn dot(&self) -> String {
+        let mut s = String::from("digraph cascade {\n    rankdir=LR;\n");
+        // keyed by position, card numbers needn't be unique
+        for (i, row) in self.rows.iter().enumerate() {
+            let _ = writeln!(
+                s,
+                "    row{i} [label=\"Card {}\\n{} matches\\n{} copies\"];",
+                row.card, row.matching, row.copies
+            );
+        }
+        for (i, row) in self.rows.iter().enumerate() {
+            for (source, copies) in row.sources.iter() {
+                let _ = writeln!(s, "    row{source} -> row{i} [label=\"{copies}\"];");
+            }
+        }
+        s.push_str("}\n");
+        s
+    }
+}
+
+impl fmt::Display for Cascade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>6} {:>8} {:>12}  won from",
+            "card", "matches", "copies"
+        )?;
+        for row in self.rows.iter() {
+            let sources: Vec<String> = row
+                .sources
+                .iter()
+                .map(|(source, copies)| format!("{} (x{copies})", self.rows[*source].card))
+                .collect();
+            let sources = if sources.is_empty() {
+                "-".to_string()
+            } else {
+                sources.join(", ")
+            };
+            writeln!(
+                f,
+                "{:>6} {:>8} {:>12}  {sources}",
+                row.card, row.matching, row.copies
+            )?;
+        }
+        writeln!(f, "total: {}", self.total())
+    }
+}
+
+#[test]
+fn test1() {
+    let cascade = Cascade::new(&super::cards(super::EXAMPLE));
+
+    let copies: Vec<BigUint> = cascade.rows.iter().map(|r| r.copies.clone()).collect();
+    let expected: Vec<BigUint> = [1u32, 2, 4, 8, 14, 1]
+        .into_iter()
+        .map(BigUint::from)
+        .collect();
+    assert_eq!(expected, copies);
+    assert_eq!(BigUint::from(30u32), cascade.total());
+
+    // card 5 is won by the original of card 1 plus every copy of cards 3 and 4
+    let sources: Vec<(usize, BigUint)> = [(0, 1u32), (2, 4), (3, 8)]
+        .into_iter()
+        .map(|(row, copies)| (row, BigUint::from(copies)))
+        .collect();
+    assert_eq!(sources, cascade.rows[4].sources);
+    assert!(cascade.rows[5].sources.is_empty());
+}
+
+#[test]
+fn test2() {
+    let cascade = Cascade::new(&super::cards(super::EXAMPLE));
+
+    let dot = cascade.dot();
+    assert!(dot.starts_with("digraph cascade {"));
+    assert!(dot.contains("row3 -> row4 [label=\"8\"];"));
+    // one edge per (winner, won) pair: 4 + 2 + 2 + 1
+    assert_eq!(9, dot.matches("->").count());
+
+    let table = cascade.to_string();
+    assert!(table.contains("1 (x1), 3 (x4), 4 (x8)"));
+    assert!(table.ends_with("total: 30\n"));
+}
+
+#[test]
+fn test3() {
+    // two cards numbered 1 still make two nodes
+    let cascade = Cascade::new(&super::cards("Card 1: 5 | 5\nCard 1: 6 | 7"));
+    let dot = cascade.dot();
+    assert!(dot.contains("row0 [label=\"Card 1\\n1 matches\\n1 copies\"];"));
+    assert!(dot.contains("row1 [label=\"Card 1\\n0 matches\\n2 copies\"];"));
+    assert!(dot.contains("row0 -> row1 [label=\"1\"];"));
+    assert!(cascade.to_string().contains("1 (x1)"));
+}
@@ -1,14 +1,28 @@
-use std::{ops::Range, str::FromStr};
+use std::{collections::HashMap, ops::Range, str::FromStr};
+
+use anyhow::{anyhow, bail};
 
 use crate::util::read_input;
 
-#[derive(Debug)]
+// one line of a map: `src` converts to the same length starting at `dst`
+#[derive(Debug, Clone, PartialEq)]
+struct Interval {
+    src: Range<u128>,
+    dst: u128,
+}
+
+impl Interval {
+    fn convert(&self, n: u128) -> u128 {
+        self.dst + (n - self.src.start)
+    }
+}
+
+#[derive(Debug, Clone)]
 struct ConversionMap {
     from: String,
     to: String,
-    src_ranges: Vec<Range<u128>>,
-    dst_ranges: Vec<Range<u128>>,
-    range_lens: Vec<u128>,
+    // sorted by source start, source ranges never overlap
+    intervals: Vec<Interval>,
 }
 
 impl ConversionMap {
@@ -16,58 +30,207 @@ impl ConversionMap {
         ConversionMap {
             from: from.to_owned(),
             to: to.to_owned(),
-            src_ranges: Vec::new(),
-            dst_ranges: Vec::new(),
-            range_lens: Vec::new(),
+            intervals: Vec::new(),
         }
     }
 
-    fn insert(&mut self, conversion: Conversion) {
-        let dest_end = conversion.dest_start + conversion.range_len;
-        let dest_range = conversion.dest_start..dest_end;
+    fn insert(&mut self, conversion: Conversion) -> anyhow::Result<()> {
+        if conversion.range_len == 0 {
+            return Ok(());
+        }
+        let Some(source_end) = conversion.source_start.checked_add(conversion.range_len) else {
+            bail!(
+                "source range starting at {} overflows",
+                conversion.source_start
+            );
+        };
+        if conversion
+            .dest_start
+            .checked_add(conversion.range_len)
+            .is_none()
+        {
+            bail!(
+                "destination range starting at {} overflows",
+                conversion.dest_start
+            );
+        }
+        let interval = Interval {
+            src: conversion.source_start..source_end,
+            dst: conversion.dest_start,
+        };
+
+        let i = self
+            .intervals
+            .partition_point(|other| other.src.start < interval.src.start);
+        let neighbours = [i.checked_sub(1), Some(i)];
+        for other in neighbours
+            .into_iter()
+            .flatten()
+            .filter_map(|j| self.intervals.get(j))
+        {
+            if other.src.start < interval.src.end && interval.src.start < other.src.end {
+                bail!(
+                    "{}-to-{} map: source range {:?} overlaps {:?}",
+                    self.from,
+                    self.to,
+                    interval.src,
+                    other.src
+                );
+            }
+        }
 
-        let source_end = conversion.source_start + conversion.range_len;
-        let source_range = conversion.source_start..source_end;
+        self.intervals.insert(i, interval);
+        Ok(())
+    }
 
-        self.src_ranges.push(source_range);
-        self.dst_ranges.push(dest_range);
-        self.range_lens.push(conversion.range_len);
+    // the interval holding `n`
+    fn find(&self, n: u128) -> Option<&Interval> {
+        let i = self
+            .intervals
+            .partition_point(|interval| interval.src.start <= n);
+        let interval = self.intervals.get(i.checked_sub(1)?)?;
+        interval.src.contains(&n).then_some(interval)
     }
 
-    fn get_range(&self, range: Range<u128>) -> Range<u128> {
-        for (i, src_range) in self.src_ranges.iter().enumerate() {
-            if src_range.start >= range.start
-                && range.end <= src_range.end
-                && range.end >= src_range.start
-            {
-                println!("{:?} is in {:?}", range, src_range);
-                let diff = range.end - src_range.start;
-                if diff == 0 {
-                    return self.dst_ranges[i].clone();
-                } else {
-                    return (self.dst_ranges[i].start + diff)..(self.dst_ranges[i].end + diff);
-                }
+    // splits `range` against the source ranges, uncovered parts map to themselves
+    fn get_range(&self, range: Range<u128>) -> Vec<Range<u128>> {
+        let mut mapped = vec![];
+        let mut cursor = range.start;
+
+        let first = self
+            .intervals
+            .partition_point(|interval| interval.src.end <= range.start);
+        for interval in self.intervals[first..].iter() {
+            if interval.src.start >= range.end {
+                break;
+            }
+            if cursor < interval.src.start {
+                mapped.push(cursor..interval.src.start);
             }
+            let start = cursor.max(interval.src.start);
+            let end = range.end.min(interval.src.end);
+            mapped.push(interval.convert(start)..interval.convert(start) + (end - start));
+            cursor = end;
         }
-        range
+        if cursor < range.end {
+            mapped.push(cursor..range.end);
+        }
+
+        merge(mapped)
     }
 
-    fn get(&self, n: u128) -> u128 {
-        for (i, range) in self.src_ranges.iter().enumerate() {
-            if range.contains(&n) {
-                let diff = n - range.start;
-                if diff == 0 {
-                    return self.dst_ranges[i].start;
-                } else {
-                    return self.dst_ranges[i].start + diff;
+    // the gaps between source ranges, up to one short of u128::MAX
+    fn gaps(&self) -> Vec<Range<u128>> {
+        let mut gaps = vec![];
+        let mut cursor = 0;
+        for interval in self.intervals.iter() {
+            if cursor < interval.src.start {
+                gaps.push(cursor..interval.src.start);
+            }
+            cursor = interval.src.end;
+        }
+        if cursor < u128::MAX {
+            gaps.push(cursor..u128::MAX);
+        }
+        gaps
+    }
+
+    // intervals and gaps, in source order
+    fn pieces(&self) -> Vec<Interval> {
+        let mut pieces: Vec<Interval> = self
+            .gaps()
+            .into_iter()
+            .map(|gap| Interval {
+                dst: gap.start,
+                src: gap,
+            })
+            .chain(self.intervals.iter().cloned())
+            .collect();
+        pieces.sort_by_key(|piece| piece.src.start);
+        pieces
+    }
+
+    // `self` then `other`, as a single map
+    fn compose(&self, other: &ConversionMap) -> ConversionMap {
+        let mut composed = ConversionMap::new(&self.from, &other.to);
+        let other_pieces = other.pieces();
+
+        for piece in self.pieces() {
+            let image = piece.dst..(piece.dst + (piece.src.end - piece.src.start));
+            for other_piece in other_pieces.iter() {
+                let start = image.start.max(other_piece.src.start);
+                let end = image.end.min(other_piece.src.end);
+                if start >= end {
+                    continue;
+                }
+
+                let source_start = piece.src.start + (start - piece.dst);
+                let dest_start = other_piece.convert(start);
+                if source_start != dest_start {
+                    composed
+                        .insert(Conversion {
+                            dest_start,
+                            source_start,
+                            range_len: end - start,
+                        })
+                        .expect("pieces of a map don't overlap");
                 }
             }
         }
 
-        n
+        composed
+    }
+
+    fn invert(&self) -> anyhow::Result<ConversionMap> {
+        let mut images: Vec<Range<u128>> = self
+            .pieces()
+            .into_iter()
+            .map(|piece| piece.dst..(piece.dst + (piece.src.end - piece.src.start)))
+            .collect();
+        images.sort_by_key(|r| r.start);
+        for pair in images.windows(2) {
+            if pair[1].start < pair[0].end {
+                bail!(
+                    "{}-to-{} map is not one-to-one, {:?} and {:?} overlap",
+                    self.from,
+                    self.to,
+                    pair[0],
+                    pair[1]
+                );
+            }
+        }
+
+        let mut inverse = ConversionMap::new(&self.to, &self.from);
+        for interval in self.intervals.iter() {
+            inverse.insert(Conversion {
+                dest_start: interval.src.start,
+                source_start: interval.dst,
+                range_len: interval.src.end - interval.src.start,
+            })?;
+        }
+        Ok(inverse)
+    }
+
+    fn get(&self, n: u128) -> u128 {
+        self.find(n).map_or(n, |interval| interval.convert(n))
     }
 }
 
+// sorted, with overlapping and touching ranges joined up
+fn merge(mut ranges: Vec<Range<u128>>) -> Vec<Range<u128>> {
+    ranges.retain(|r| !r.is_empty());
+    ranges.sort_by_key(|r| r.start);
+
+    let mut merged: Vec<Range<u128>> = vec![];
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
 #[derive(Debug)]
 struct Conversion {
     dest_start: u128,
@@ -79,74 +242,152 @@ impl FromStr for Conversion {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> anyhow::Result<Self> {
-        let (dest_start, rest) = s.split_once(" ").unwrap();
-        let (source_start, range_len) = rest.split_once(" ").unwrap();
+        let numbers: Vec<&str> = s.split_whitespace().collect();
+        let [dest_start, source_start, range_len] = numbers[..] else {
+            bail!("expected <destination> <source> <length>, got {s:?}");
+        };
+        let parse = |n: &str| -> anyhow::Result<u128> {
+            n.parse().map_err(|_| anyhow!("{n:?} is not a number"))
+        };
 
         Ok(Conversion {
-            dest_start: dest_start.parse().unwrap(),
-            source_start: source_start.parse().unwrap(),
-            range_len: range_len.parse().unwrap(),
+            dest_start: parse(dest_start)?,
+            source_start: parse(source_start)?,
+            range_len: parse(range_len)?,
         })
     }
 }
 
-fn preprocess(input: &str) -> (Vec<u128>, Vec<ConversionMap>) {
-    let mut seeds: Vec<u128> = vec![];
-    let mut maps: Vec<ConversionMap> = vec![];
-    let mut current_map = None;
-    let mut in_map = false;
-
-    let lines: Vec<_> = input.lines().collect();
+// maps by the category they convert from, in whatever order they were listed
+struct Categories<'a>(HashMap<&'a str, Vec<&'a ConversionMap>>);
 
-    for (i, line) in lines.iter().enumerate() {
-        let line = line.trim();
+impl<'a> Categories<'a> {
+    fn new(maps: &'a [ConversionMap]) -> Self {
+        let mut graph: HashMap<&str, Vec<&ConversionMap>> = HashMap::new();
+        for map in maps.iter() {
+            graph.entry(map.from.as_str()).or_default().push(map);
+        }
+        Categories(graph)
+    }
 
-        // println!("line {i} ({in_map}): {}", line);
+    // the one route of maps from `from` to `to`
+    fn route(&self, from: &str, to: &str) -> anyhow::Result<Vec<&'a ConversionMap>> {
+        let mut routes = vec![];
+        self.walk(from, to, &mut vec![], &mut routes)?;
+
+        match routes.len() {
+            0 => bail!("no maps lead from {from} to {to}"),
+            1 => Ok(routes.pop().unwrap()),
+            _ => {
+                let routes: Vec<String> = routes
+                    .iter()
+                    .map(|route| {
+                        let mut names = vec![from];
+                        names.extend(route.iter().map(|map| map.to.as_str()));
+                        names.join(" -> ")
+                    })
+                    .collect();
+                bail!(
+                    "more than one way from {from} to {to}: {}",
+                    routes.join(", ")
+                )
+            }
+        }
+    }
 
-        if i == 0 {
-            let (_, list) = line.split_once(":").unwrap();
-            seeds = list
-                .trim()
-                .split_whitespace()
-                .map(|s| s.parse().unwrap())
-                .collect();
-            continue;
+    fn walk(
+        &self,
+        category: &str,
+        to: &str,
+        path: &mut Vec<&'a ConversionMap>,
+        routes: &mut Vec<Vec<&'a ConversionMap>>,
+    ) -> anyhow::Result<()> {
+        if category == to {
+            routes.push(path.clone());
+            return Ok(());
         }
 
-        if i == 1 {
-            continue;
+        for map in self.0.get(category).into_iter().flatten() {
+            if let Some(i) = path.iter().position(|m| m.from == map.to) {
+                let mut names: Vec<&str> = path[i..].iter().map(|m| m.from.as_str()).collect();
+                names.extend([category, map.to.as_str()]);
+                bail!("maps form a cycle: {}", names.join(" -> "));
+            }
+            if map.to == map.from {
+                bail!("{0}-to-{0} map forms a cycle", map.from);
+            }
+
+            path.push(map);
+            self.walk(&map.to, to, path, routes)?;
+            path.pop();
         }
+        Ok(())
+    }
+}
+
+// `<from>-to-<to> map:`
+fn parse_header(header: &str) -> Option<(&str, &str)> {
+    let header = header.strip_suffix(':')?;
+    let [name, "map"] = header.split_whitespace().collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    let (from, to) = name.split_once("-to-")?;
+    (!from.is_empty() && !to.is_empty()).then_some((from, to))
+}
+
+// blank lines, indentation and `#` comments are ignored, a map runs until the next header
+fn preprocess(input: &str) -> anyhow::Result<(Vec<u128>, Vec<ConversionMap>)> {
+    let mut seeds: Option<Vec<u128>> = None;
+    let mut maps: Vec<ConversionMap> = vec![];
 
+    for (i, line) in input.lines().enumerate() {
+        let number = i + 1;
+        let line = line.split('#').next().unwrap_or_default().trim();
         if line.is_empty() {
-            maps.push(current_map.take().unwrap());
-            in_map = false;
             continue;
         }
 
-        if in_map == false {
-            in_map = true;
-            let (left, _) = line.split_once(" ").unwrap();
-            let (from, rest) = left.split_once("-").unwrap();
-            let (_, to) = rest.split_once("-").unwrap();
-            current_map = Some(ConversionMap::new(from, to));
-        } else {
-            let conversion = Conversion::from_str(line).unwrap();
-            if let Some(ref mut map) = current_map {
-                map.insert(conversion);
-            }
-        }
+        let parse_seeds = |list: &str| -> anyhow::Result<Vec<u128>> {
+            list.split_whitespace()
+                .map(|s| s.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|_| anyhow!("line {number}: bad seed list {list:?}"))
+        };
 
-        if i == lines.len() - 1 {
-            maps.push(current_map.take().unwrap());
+        if let Some(list) = line.strip_prefix("seeds:") {
+            if seeds.is_some() {
+                bail!("line {number}: seeds are listed twice");
+            }
+            seeds = Some(parse_seeds(list)?);
+        } else if line.ends_with(':') {
+            let Some((from, to)) = parse_header(line) else {
+                bail!("line {number}: expected a <from>-to-<to> map: header, got {line:?}");
+            };
+            maps.push(ConversionMap::new(from, to));
+        } else if let (Some(seeds), true) = (seeds.as_mut(), maps.is_empty()) {
+            // the seed list carries on over several lines
+            seeds.extend(parse_seeds(line)?);
+        } else {
+            let Some(map) = maps.last_mut() else {
+                bail!("line {number}: {line:?} comes before any header");
+            };
+            let conversion =
+                Conversion::from_str(line).map_err(|e| anyhow!("line {number}: {e}"))?;
+            map.insert(conversion)
+                .map_err(|e| anyhow!("line {number}: {e}"))?;
         }
     }
 
-    (seeds, maps)
+    let Some(seeds) = seeds else {
+        bail!("no seeds: line");
+    };
+    Ok((seeds, maps))
 }
 
 fn solve1(input: &str) -> u128 {
-    let (mut seeds, maps) = preprocess(input);
-    for map in maps.iter() {
+    let (mut seeds, maps) = preprocess(input).unwrap();
+    let route = Categories::new(&maps).route("seed", "location").unwrap();
+    for map in route {
         for seed in seeds.iter_mut() {
             *seed = map.get(*seed);
         }
@@ -154,32 +395,97 @@ fn solve1(input: &str) -> u128 {
     *seeds.iter().min().unwrap()
 }
 
-fn solve2(input: &str) -> u128 {
-    let (seeds, maps) = preprocess(input);
-    let mut seeds: Vec<Range<u128>> = seeds
+// part 2 reads the seeds as pairs of start and length
+fn seed_ranges(seeds: &[u128]) -> anyhow::Result<Vec<Range<u128>>> {
+    if seeds.len() % 2 == 1 {
+        bail!(
+            "seed {} has no length to go with it",
+            seeds[seeds.len() - 1]
+        );
+    }
+    seeds
         .chunks(2)
-        .map(|chunk| chunk[0]..(chunk[0] + chunk[1] - 1))
-        .collect();
-    println!("{:?}", seeds);
-
-    for map in maps.iter() {
-        for seed_range in seeds.iter_mut() {
-            let start = map.get(seed_range.start);
-            let end = map.get(seed_range.end);
-            //println!("new_range: {:?}", start..end);
-            *seed_range = start..end;
+        .map(|chunk| match chunk[0].checked_add(chunk[1]) {
+            Some(end) => Ok(chunk[0]..end),
+            None => bail!("seed range {} +{} overflows", chunk[0], chunk[1]),
+        })
+        .collect()
+}
+
+fn solve2(input: &str) -> anyhow::Result<u128> {
+    let (seeds, maps) = preprocess(input)?;
+    let mut ranges = seed_ranges(&seeds)?;
+
+    let route = Categories::new(&maps).route("seed", "location")?;
+    for map in route {
+        ranges = merge(
+            ranges
+                .into_iter()
+                .flat_map(|range| map.get_range(range))
+                .collect(),
+        );
+    }
+
+    let lowest = ranges.first().ok_or_else(|| anyhow!("no seed ranges"))?;
+    Ok(lowest.start)
+}
+
+fn collapse(maps: &[&ConversionMap]) -> Option<ConversionMap> {
+    let (first, rest) = maps.split_first()?;
+    Some(
+        rest.iter()
+            .fold((*first).clone(), |chain, map| chain.compose(map)),
+    )
+}
+
+// walks the collapsed chain back from location 0, returns (seed, location)
+fn lowest_seed(input: &str) -> anyhow::Result<(u128, u128)> {
+    let (seeds, maps) = preprocess(input)?;
+    let ranges = seed_ranges(&seeds)?;
+    let route = Categories::new(&maps).route("seed", "location")?;
+    let Some(chain) = collapse(&route) else {
+        bail!("almanac has no maps");
+    };
+    let inverse = chain.invert()?;
+
+    for piece in inverse.pieces() {
+        let image = piece.dst..(piece.dst + (piece.src.end - piece.src.start));
+        let lowest = ranges
+            .iter()
+            .map(|range| image.start.max(range.start)..image.end.min(range.end))
+            .filter(|r| !r.is_empty())
+            .map(|r| r.start)
+            .min();
+        if let Some(seed) = lowest {
+            return Ok((seed, piece.src.start + (seed - piece.dst)));
         }
     }
 
-    let mut number = seeds.iter().map(|r| r.start).min().unwrap();
-    println!(" seeds at the end: {:?}", seeds);
-    println!("lowest number start: {:?}", number);
-    for map in maps.iter() {
-        number = map.get(number);
-        println!("corresponds to: {} {:?}", map.to, number);
+    bail!("no seed ranges")
+}
+
+pub fn lowest(args: &[String]) -> anyhow::Result<()> {
+    let [] = args else {
+        bail!("expected no arguments");
+    };
+    let input = read_input(5);
+    let (seed, location) = lowest_seed(&input)?;
+    println!("day05 lowest: seed {seed} reaches location {location}");
+    Ok(())
+}
+
+pub fn convert(args: &[String]) -> anyhow::Result<()> {
+    let [from, to, numbers @ ..] = args else {
+        bail!("expected <from> <to> <number>...");
+    };
+    let input = read_input(5);
+    let (_, maps) = preprocess(&input)?;
+    let route = Categories::new(&maps).route(from, to)?;
+    for n in numbers {
+        let converted = route.iter().fold(n.parse()?, |n, map| map.get(n));
+        println!("{from} {n} -> {to} {converted}");
     }
-    number
-    //seeds.iter().map(|r| r.start).min().unwrap()
+    Ok(())
 }
 
 pub fn answer1() {
@@ -187,9 +493,10 @@ pub fn answer1() {
     println!("day05 part1: {}", solve1(&input));
 }
 
-pub fn answer2() {
+pub fn answer2() -> anyhow::Result<()> {
     let input = read_input(5);
-    println!("day05 part2: {}", solve2(&input));
+    println!("day05 part2: {}", solve2(&input)?);
+    Ok(())
 }
 
 #[test]
@@ -228,7 +535,7 @@ fn test1() {
     60 56 37
     56 93 4";
 
-    let (seeds, maps) = preprocess(input);
+    let (seeds, maps) = preprocess(input).unwrap();
     assert_eq!(maps[0].get(seeds[0]), 81);
     assert_eq!(maps[0].get(seeds[1]), 14);
     assert_eq!(maps[0].get(seeds[2]), 57);
@@ -273,5 +580,351 @@ fn test2() {
     60 56 37
     56 93 4";
 
-    assert_eq!(46, solve2(input));
+    assert_eq!(46, solve2(input).unwrap());
+}
+
+#[test]
+fn test4() {
+    let input = "seeds: 79 14 55 13
+
+    seed-to-soil map:
+    50 98 2
+    52 50 48
+
+    soil-to-fertilizer map:
+    0 15 37
+    37 52 2
+    39 0 15
+
+    fertilizer-to-water map:
+    49 53 8
+    0 11 42
+    42 0 7
+    57 7 4
+
+    water-to-light map:
+    88 18 7
+    18 25 70
+
+    light-to-temperature map:
+    45 77 23
+    81 45 19
+    68 64 13
+
+    temperature-to-humidity map:
+    0 69 1
+    1 0 69
+
+    humidity-to-location map:
+    60 56 37
+    56 93 4";
+
+    let (_, maps) = preprocess(input).unwrap();
+    let chain = collapse(&maps.iter().collect::<Vec<_>>()).unwrap();
+    assert_eq!(
+        ("seed", "location"),
+        (chain.from.as_str(), chain.to.as_str())
+    );
+    for n in 0..200 {
+        assert_eq!(maps.iter().fold(n, |n, map| map.get(n)), chain.get(n));
+    }
+
+    let inverse = chain.invert().unwrap();
+    for n in 0..200 {
+        assert_eq!(n, inverse.get(chain.get(n)));
+    }
+
+    assert_eq!((82, 46), lowest_seed(input).unwrap());
+}
+
+#[cfg(test)]
+fn generate(maps: usize, seed: u64) -> String {
+    let mut next = crate::util::xorshift(seed);
+
+    let mut seeds = vec![];
+    for _ in 0..3 {
+        seeds.push((next() % 100).to_string());
+        seeds.push((1 + next() % 30).to_string());
+    }
+    let mut s = format!("seeds: {}\n", seeds.join(" "));
+
+    let categories = ["seed", "soil", "fertilizer", "water", "light", "location"];
+    for i in 0..maps {
+        s.push_str(&format!(
+            "\n{}-to-{} map:\n",
+            categories[i],
+            categories[i + 1]
+        ));
+
+        // source ranges must not overlap, so walk along and keep some of the segments
+        let mut start = next() % 10;
+        while start < 120 {
+            let len = 1 + next() % 25;
+            if !next().is_multiple_of(3) {
+                s.push_str(&format!("{} {start} {len}\n", next() % 150));
+            }
+            start += len + next() % 5;
+        }
+    }
+    s.trim_end().to_string()
+}
+
+#[test]
+fn test3() {
+    for seed in 1..50 {
+        let input = generate(5, seed);
+        let (seeds, maps) = preprocess(&input).unwrap();
+
+        // every map on its own: the mapped ranges hold exactly the mapped values
+        for map in maps.iter() {
+            for chunk in seeds.chunks(2) {
+                let range = chunk[0]..(chunk[0] + chunk[1]);
+                let mut expected: Vec<u128> = range.clone().map(|n| map.get(n)).collect();
+                expected.sort();
+                expected.dedup();
+
+                let mapped: Vec<u128> = map.get_range(range).into_iter().flatten().collect();
+                assert_eq!(expected, mapped, "{input}");
+            }
+        }
+
+        let brute_force = seeds
+            .chunks(2)
+            .flat_map(|chunk| chunk[0]..(chunk[0] + chunk[1]))
+            .map(|n| maps.iter().fold(n, |n, map| map.get(n)))
+            .min()
+            .unwrap();
+        assert_eq!(brute_force, solve2(&input).unwrap(), "{input}");
+    }
+}
+
+#[test]
+fn test5() {
+    for seed in 1..50 {
+        let input = generate(5, seed);
+        let (_, maps) = preprocess(&input).unwrap();
+        let chain = collapse(&maps.iter().collect::<Vec<_>>()).unwrap();
+        for n in 0..200 {
+            assert_eq!(maps.iter().fold(n, |n, map| map.get(n)), chain.get(n));
+        }
+    }
+
+    // 10..20 moves onto 20..30, which the identity gap above it also covers
+    let mut map = ConversionMap::new("seed", "soil");
+    map.insert(Conversion::from_str("20 10 10").unwrap())
+        .unwrap();
+    assert!(map.invert().is_err());
+}
+
+#[test]
+fn test6() {
+    // the example with its maps listed back to front
+    let input = "seeds: 79 14 55 13
+
+    humidity-to-location map:
+    60 56 37
+    56 93 4
+
+    temperature-to-humidity map:
+    0 69 1
+    1 0 69
+
+    light-to-temperature map:
+    45 77 23
+    81 45 19
+    68 64 13
+
+    water-to-light map:
+    88 18 7
+    18 25 70
+
+    fertilizer-to-water map:
+    49 53 8
+    0 11 42
+    42 0 7
+    57 7 4
+
+    soil-to-fertilizer map:
+    0 15 37
+    37 52 2
+    39 0 15
+
+    seed-to-soil map:
+    50 98 2
+    52 50 48";
+
+    assert_eq!(35, solve1(input));
+    assert_eq!(46, solve2(input).unwrap());
+
+    let (_, maps) = preprocess(input).unwrap();
+    let categories = Categories::new(&maps);
+    let route = categories.route("seed", "humidity").unwrap();
+    assert_eq!(6, route.len());
+    // seed 79 is humidity 78 in the puzzle's walkthrough
+    assert_eq!(78, route.iter().fold(79, |n, map| map.get(n)));
+
+    let error = categories.route("location", "seed").unwrap_err();
+    assert_eq!("no maps lead from location to seed", error.to_string());
+}
+
+#[test]
+fn test7() {
+    let maps = |names: &[(&str, &str)]| -> Vec<ConversionMap> {
+        names
+            .iter()
+            .map(|(from, to)| ConversionMap::new(from, to))
+            .collect()
+    };
+
+    let ambiguous = maps(&[
+        ("seed", "soil"),
+        ("soil", "water"),
+        ("seed", "fertilizer"),
+        ("fertilizer", "water"),
+    ]);
+    let error = Categories::new(&ambiguous)
+        .route("seed", "water")
+        .unwrap_err();
+    assert_eq!(
+        "more than one way from seed to water: seed -> soil -> water, seed -> fertilizer -> water",
+        error.to_string()
+    );
+
+    let cycle = maps(&[
+        ("seed", "soil"),
+        ("soil", "water"),
+        ("water", "soil"),
+        ("water", "location"),
+    ]);
+    let error = Categories::new(&cycle)
+        .route("seed", "location")
+        .unwrap_err();
+    assert_eq!(
+        "maps form a cycle: soil -> water -> soil",
+        error.to_string()
+    );
+}
+
+#[test]
+fn test8() {
+    let mut map = ConversionMap::new("seed", "soil");
+    for line in ["52 50 48", "50 98 2", "0 10 5"] {
+        map.insert(Conversion::from_str(line).unwrap()).unwrap();
+    }
+    let starts: Vec<u128> = map.intervals.iter().map(|i| i.src.start).collect();
+    assert_eq!(vec![10, 50, 98], starts);
+    assert_eq!(vec![0..10, 15..50, 100..u128::MAX], map.gaps());
+
+    assert_eq!(0, map.get(10));
+    assert_eq!(2, map.get(12));
+    assert_eq!(15, map.get(15));
+    assert_eq!(99, map.get(97));
+    assert_eq!(51, map.get(99));
+    assert_eq!(100, map.get(100));
+
+    let error = map
+        .insert(Conversion::from_str("0 14 3").unwrap())
+        .unwrap_err();
+    assert_eq!(
+        "seed-to-soil map: source range 14..17 overlaps 10..15",
+        error.to_string()
+    );
+    assert!(map
+        .insert(Conversion::from_str("0 40 11").unwrap())
+        .is_err());
+    // touching is fine
+    map.insert(Conversion::from_str("0 40 10").unwrap())
+        .unwrap();
+    assert_eq!(vec![0..10, 15..40, 100..u128::MAX], map.gaps());
+}
+
+#[test]
+fn test9() {
+    // odd spacing, comments, runs of blank lines and no final newline
+    let input = "
+
+    # the puzzle example
+    seeds:   79 14
+             55 13   # two ranges
+    seed-to-soil   map:
+    50 98 2
+    52    50 48
+
+
+    soil-to-fertilizer map:
+    0 15 37
+    37 52 2
+    39 0 15
+    fertilizer-to-water map:
+    49 53 8
+    0 11 42
+    42 0 7
+    57 7 4
+
+    water-to-light map:
+    88 18 7
+    18 25 70
+
+    light-to-temperature map:
+    45 77 23
+    81 45 19
+    68 64 13
+
+    temperature-to-humidity map:
+    0 69 1
+    1 0 69
+    humidity-to-location map:
+    60 56 37
+    56 93 4   ";
+
+    let (seeds, maps) = preprocess(input).unwrap();
+    assert_eq!(vec![79, 14, 55, 13], seeds);
+    assert_eq!(7, maps.len());
+    assert_eq!(35, solve1(input));
+    assert_eq!(46, solve2(input).unwrap());
+}
+
+#[test]
+fn test10() {
+    let error = |input: &str| preprocess(input).unwrap_err().to_string();
+
+    assert_eq!(
+        "line 3: expected a <from>-to-<to> map: header, got \"seed-soil map:\"",
+        error("seeds: 1 2\n\nseed-soil map:\n1 2 3")
+    );
+    assert_eq!(
+        "line 4: expected <destination> <source> <length>, got \"1 2\"",
+        error("seeds: 1 2\nseed-to-soil map:\n1 2 3\n1 2")
+    );
+    assert_eq!(
+        "line 3: \"x\" is not a number",
+        error("seeds: 1 2\nseed-to-soil map:\n1 x 3")
+    );
+    assert_eq!(
+        "line 1: \"1 2 3\" comes before any header",
+        error("1 2 3\nseeds: 1 2")
+    );
+    assert_eq!(
+        "line 4: seed-to-soil map: source range 3..5 overlaps 2..5",
+        error("seeds: 1 2\nseed-to-soil map:\n1 2 3\n9 3 2")
+    );
+    assert_eq!("no seeds: line", error("seed-to-soil map:\n1 2 3"));
+
+    // part 2 pairs the seeds up, so a stray one is an error rather than a panic
+    let input = "seeds: 79 14 55\n\nseed-to-location map:\n0 0 100";
+    assert_eq!(
+        "seed 55 has no length to go with it",
+        lowest_seed(input).unwrap_err().to_string()
+    );
+    let input = format!("seeds: {} 2\n\nseed-to-location map:\n0 0 100", u128::MAX);
+    assert!(lowest_seed(&input).is_err());
+    assert!(lowest(&["--dot".to_string()]).is_err());
+
+    // an empty range leaves nothing to take the lowest of
+    let input = "seeds: 5 0\n\nseed-to-location map:\n0 0 100";
+    assert_eq!("no seed ranges", solve2(input).unwrap_err().to_string());
+    assert_eq!(
+        "no seed ranges",
+        lowest_seed(input).unwrap_err().to_string()
+    );
 }
@@ -1,89 +1,403 @@
+use std::{fs, ops::RangeInclusive, str::FromStr};
+
+use anyhow::{anyhow, bail};
+use num_bigint::{BigInt, BigUint};
+
 use crate::util::read_input;
 
+use self::report::Format;
+
+mod report;
+
+// u64 in, u128 or big integers for anything derived
 #[derive(Debug)]
 struct Race {
-    lasts: usize,
-    distance: usize,
+    lasts: u64,
+    distance: u64,
 }
 
-fn race(s: &str) -> Race {
-    let (time, distance) = s.split_once("\n").unwrap();
-    let (_, nums) = time.split_once(": ").unwrap();
-    let mut lasts = String::new();
-    for n in nums.split_whitespace() {
-        lasts.push_str(n);
+fn number(what: &str, digits: &str) -> anyhow::Result<u64> {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        bail!("{what} {digits:?} is not a number");
     }
+    digits.parse().map_err(|_| {
+        anyhow!(
+            "{what} {digits} is too large, the most supported is {}",
+            u64::MAX
+        )
+    })
+}
 
-    let (_, nums) = distance.split_once(": ").unwrap();
-    let mut distance = String::new();
-    for n in nums.split_whitespace() {
-        distance.push_str(n);
-    }
+fn lines(s: &str) -> anyhow::Result<(&str, &str)> {
+    let mut lines = s.lines().map(str::trim).filter(|line| !line.is_empty());
+    let (Some(time), Some(distance)) = (lines.next(), lines.next()) else {
+        bail!("expected a Time: line and a Distance: line");
+    };
+    let (Some(times), Some(distances)) = (
+        time.strip_prefix("Time:"),
+        distance.strip_prefix("Distance:"),
+    ) else {
+        bail!("expected a Time: line and a Distance: line");
+    };
+    Ok((times, distances))
+}
 
-    Race {
-        lasts: lasts.parse().unwrap(),
-        distance: distance.parse().unwrap(),
-    }
+fn race(s: &str) -> anyhow::Result<Race> {
+    let (times, distances) = lines(s)?;
+    let lasts: String = times.split_whitespace().collect();
+    let distance: String = distances.split_whitespace().collect();
+
+    Ok(Race {
+        lasts: number("race time", &lasts)?,
+        distance: number("record distance", &distance)?,
+    })
 }
 
-fn races(s: &str) -> Vec<Race> {
-    let (times, distances) = s.split_once("\n").unwrap();
+fn races(s: &str) -> anyhow::Result<Vec<Race>> {
+    let (times, distances) = lines(s)?;
+    let times: Vec<&str> = times.split_whitespace().collect();
+    let distances: Vec<&str> = distances.split_whitespace().collect();
+    if times.len() != distances.len() {
+        bail!(
+            "{} race times but {} record distances",
+            times.len(),
+            distances.len()
+        );
+    }
 
     times
-        .split_whitespace()
-        .zip(distances.split_whitespace())
-        .skip(1)
-        .map(|(t, d)| Race {
-            lasts: t.parse().unwrap(),
-            distance: d.parse().unwrap(),
+        .iter()
+        .zip(distances.iter())
+        .map(|(t, d)| {
+            Ok(Race {
+                lasts: number("race time", t)?,
+                distance: number("record distance", d)?,
+            })
         })
         .collect()
 }
 
+#[derive(Debug, Clone, PartialEq)]
 struct Boat {
-    inital_speed: usize,
-    acceleration: usize,
+    initial_speed: u64,
+    // speed gained per ms the button is held, once charging has started
+    acceleration: u64,
+    max_speed: Option<u64>,
+    // ms the button has to be held before the boat starts charging
+    delay: u64,
+}
+
+impl Default for Boat {
+    fn default() -> Self {
+        Boat {
+            initial_speed: 0,
+            acceleration: 1,
+            max_speed: None,
+            delay: 0,
+        }
+    }
 }
 
 impl Boat {
-    fn simulate(&mut self, race: &Race, hold_button: usize) -> bool {
-        let speed = self.inital_speed + self.acceleration * hold_button;
-        let time_left = race.lasts - hold_button;
-        let moved = time_left * speed;
+    // u64 + u64 * u64 always fits
+    fn speed(&self, hold_button: u64) -> u128 {
+        let charged = hold_button.saturating_sub(self.delay) as u128;
+        let speed = self.initial_speed as u128 + self.acceleration as u128 * charged;
+        self.max_speed.map_or(speed, |max| speed.min(max as u128))
+    }
+
+    fn simulate(&self, race: &Race, hold_button: u64) -> bool {
+        let time_left = (race.lasts - hold_button) as u128;
+        // too far to count is certainly past the record
+        time_left
+            .checked_mul(self.speed(hold_button))
+            .is_none_or(|moved| moved > race.distance as u128)
+    }
+
+    fn simulated(&self, race: &Race) -> u128 {
+        (0..=race.lasts).filter(|n| self.simulate(race, *n)).count() as u128
+    }
+
+    fn distance(&self, race: &Race, hold_button: u64) -> BigUint {
+        BigUint::from(race.lasts - hold_button) * BigUint::from(self.speed(hold_button))
+    }
+
+    // initial speed up to `charging_end`, max speed from `capped`, linear in between
+    fn stretches(&self) -> (u128, u128) {
+        let capped = match self.max_speed {
+            Some(max) if self.acceleration > 0 => {
+                let charge = max
+                    .saturating_sub(self.initial_speed)
+                    .div_ceil(self.acceleration);
+                self.delay as u128 + charge as u128
+            }
+            _ => u128::MAX,
+        };
+        ((self.delay as u128).min(capped), capped)
+    }
+
+    // each stretch on its own, touching intervals joined
+    fn winning(&self, race: &Race) -> Vec<RangeInclusive<u64>> {
+        let initial_speed = self.speed(0);
+        let (charging_end, capped) = self.stretches();
+
+        let mut intervals = vec![constant_speed(race, initial_speed, 0, charging_end)];
+        if self.acceleration == 0 {
+            intervals.push(constant_speed(race, initial_speed, charging_end, capped));
+        } else if charging_end < capped {
+            intervals.push(accelerating(race, self, charging_end, capped));
+        }
+        if let Some(max) = self.max_speed.filter(|_| capped < u128::MAX) {
+            intervals.push(constant_speed(race, max as u128, capped, u128::MAX));
+        }
 
-        time_left < race.lasts && moved > race.distance
+        let mut merged: Vec<RangeInclusive<u64>> = vec![];
+        for interval in intervals.into_iter().flatten() {
+            match merged.last_mut() {
+                Some(last) if *last.end() as u128 + 1 == *interval.start() as u128 => {
+                    *last = *last.start()..=*interval.end();
+                }
+                _ => merged.push(interval),
+            }
+        }
+        merged
+    }
+
+    fn ways_to_win(&self, race: &Race) -> u128 {
+        self.winning(race)
+            .iter()
+            .map(|interval| (interval.end() - interval.start()) as u128 + 1)
+            .sum()
+    }
+
+    // the shortest hold that goes furthest, at the start of a stretch or the top of the ramp
+    fn best_hold(&self, race: &Race) -> u64 {
+        let (charging_end, capped) = self.stretches();
+        let mut candidates = vec![0, charging_end, capped];
+
+        if self.acceleration > 0 && charging_end < capped && charging_end <= race.lasts as u128 {
+            let a = BigInt::from(self.acceleration);
+            let t = BigInt::from(race.lasts - self.delay);
+            let b = &a * &t - BigInt::from(self.initial_speed);
+            let vertex = floor_div(&b, &(&a * 2)) + BigInt::from(self.delay);
+
+            let low = BigInt::from(charging_end);
+            let high = BigInt::from(capped - 1);
+            for vertex in [vertex.clone(), vertex + 1] {
+                let vertex = vertex.max(low.clone()).min(high.clone());
+                candidates.push(u128::try_from(vertex).unwrap());
+            }
+        }
+
+        candidates
+            .into_iter()
+            .filter(|h| *h <= race.lasts as u128)
+            .map(|h| h as u64)
+            .max_by(|a, b| {
+                self.distance(race, *a)
+                    .cmp(&self.distance(race, *b))
+                    .then(b.cmp(a))
+            })
+            .unwrap_or(0)
+    }
+}
+
+// winning holds in start..end at a fixed `speed`, while over distance / speed ms are left
+fn constant_speed(race: &Race, speed: u128, start: u128, end: u128) -> Option<RangeInclusive<u64>> {
+    let lasts = race.lasts as u128;
+    let end = end.min(lasts + 1);
+    if speed == 0 || start >= end {
+        return None;
+    }
+    // the last hold time that still wins is lasts - (distance / speed + 1)
+    match lasts.checked_sub(race.distance as u128 / speed + 1) {
+        Some(last) if last >= start => Some(start as u64..=last.min(end - 1) as u64),
+        _ => None,
+    }
+}
+
+// rounds towards negative infinity, `d` is positive
+fn floor_div(n: &BigInt, d: &BigInt) -> BigInt {
+    let q = n / d;
+    if n.sign() == num_bigint::Sign::Minus && &q * d != *n {
+        q - 1
+    } else {
+        q
     }
 }
 
-fn solve1(s: &str) -> usize {
-    let races = races(s);
-    let mut boat = Boat {
-        inital_speed: 0,
-        acceleration: 1,
+// winning holds in start..end while speeding up: (v + a·k)(t - k) beats the distance
+// strictly between the roots, in big integers since the discriminant needs ~256 bits
+fn accelerating(race: &Race, boat: &Boat, start: u128, end: u128) -> Option<RangeInclusive<u64>> {
+    let end = end.min(race.lasts as u128 + 1);
+    if start >= end {
+        return None;
+    }
+    let a = BigInt::from(boat.acceleration);
+    let v = BigInt::from(boat.initial_speed);
+    let t = BigInt::from(race.lasts - boat.delay);
+    let distance = BigInt::from(race.distance);
+    let wins = |k: &BigInt| (&v + &a * k) * (&t - k) > distance;
+
+    // the integer maximum sits on one side or the other of the vertex (a·t - v) / 2a
+    let b = &a * &t - &v;
+    let two_a = &a * 2;
+    let vertex = floor_div(&b, &two_a);
+    let peak = if wins(&vertex) {
+        vertex
+    } else if wins(&(&vertex + 1)) {
+        vertex + 1
+    } else {
+        return None;
     };
 
-    races
-        .iter()
-        .map(|race| {
-            (0..race.lasts + 1)
-                .into_iter()
-                .map(|n| boat.simulate(race, n) as usize)
-                .sum::<usize>()
-        })
-        .product()
+    // the square root rounds down, so both roots are at most a step or two off
+    let discriminant: BigInt = &b * &b - &two_a * 2 * (&distance - &v * &t);
+    let root = discriminant.sqrt();
+    let mut low = floor_div(&(&b - &root), &two_a).min(peak.clone());
+    while wins(&(&low - 1)) {
+        low -= 1;
+    }
+    while !wins(&low) {
+        low += 1;
+    }
+    let mut high = floor_div(&(&b + &root), &two_a).max(peak);
+    while wins(&(&high + 1)) {
+        high += 1;
+    }
+    while !wins(&high) {
+        high -= 1;
+    }
+
+    let delay = BigInt::from(boat.delay);
+    let low = (low + &delay).max(BigInt::from(start));
+    let high = (high + &delay).min(BigInt::from(end - 1));
+    if low > high {
+        None
+    } else {
+        // both ends are inside the race now, so they fit
+        Some(u64::try_from(low).unwrap()..=u64::try_from(high).unwrap())
+    }
+}
+
+// `key = value` lines, `#` starts a comment
+impl FromStr for Boat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut boat = Boat::default();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                bail!("line {}: expected key = value, got {line:?}", i + 1);
+            };
+            boat.set(key.trim(), value.trim())
+                .map_err(|e| anyhow!("line {}: {e}", i + 1))?;
+        }
+        Ok(boat)
+    }
+}
+
+impl Boat {
+    fn set(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+        let value = number(key, value)?;
+        match key {
+            "initial_speed" => self.initial_speed = value,
+            "acceleration" => self.acceleration = value,
+            "max_speed" => self.max_speed = Some(value),
+            "delay" => self.delay = value,
+            _ => bail!("unknown boat setting {key}"),
+        }
+        Ok(())
+    }
+
+    // `--config <file>` first, the settings on top, any of `switches` anywhere
+    fn from_args<'a>(
+        args: &'a [String],
+        switches: &[&str],
+    ) -> anyhow::Result<(Self, Vec<&'a str>)> {
+        let mut boat = Boat::default();
+        let mut on = vec![];
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if switches.contains(&arg.as_str()) {
+                on.push(arg.as_str());
+                continue;
+            }
+            let Some(value) = args.next() else {
+                bail!("{arg} needs a value");
+            };
+            match arg.as_str() {
+                "--config" => boat = Boat::from_str(&fs::read_to_string(value)?)?,
+                "--initial-speed" => boat.set("initial_speed", value)?,
+                "--acceleration" => boat.set("acceleration", value)?,
+                "--max-speed" => boat.set("max_speed", value)?,
+                "--delay" => boat.set("delay", value)?,
+                _ => bail!("unknown boat option {arg}"),
+            }
+        }
+        Ok((boat, on))
+    }
 }
 
-fn solve2(s: &str) -> usize {
-    let race = race(s);
-    let mut boat = Boat {
-        inital_speed: 0,
-        acceleration: 1,
+fn product(mut counts: impl Iterator<Item = u128>) -> anyhow::Result<u128> {
+    counts.try_fold(1u128, |product, count| {
+        product
+            .checked_mul(count)
+            .ok_or_else(|| anyhow!("the product of the ways to win is too large for u128"))
+    })
+}
+
+fn solve1(s: &str) -> u128 {
+    let boat = Boat::default();
+    let races = races(s).unwrap();
+    product(races.iter().map(|race| boat.ways_to_win(race))).unwrap()
+}
+
+fn solve2(s: &str) -> u128 {
+    Boat::default().ways_to_win(&race(s).unwrap())
+}
+
+// `--simulate` counts hold time by hold time instead
+pub fn boat(args: &[String]) -> anyhow::Result<()> {
+    let (boat, switches) = Boat::from_args(args, &["--simulate"])?;
+    let simulate = !switches.is_empty();
+    let count = |race: &Race| {
+        if simulate {
+            boat.simulated(race)
+        } else {
+            boat.ways_to_win(race)
+        }
     };
 
-    (0..race.lasts + 1)
-        .into_iter()
-        .map(|n| boat.simulate(&race, n) as usize)
-        .sum()
+    let input = read_input(6);
+    let part1 = product(races(&input)?.iter().map(count))?;
+    println!("day06 boat part1: {part1}");
+    println!("day06 boat part2: {}", count(&race(&input)?));
+    Ok(())
+}
+
+// `[--json] [--kerned] [boat options]`, --kerned reports the single long race from part 2
+pub fn report(args: &[String]) -> anyhow::Result<()> {
+    let (boat, switches) = Boat::from_args(args, &["--json", "--kerned"])?;
+    let format = if switches.contains(&"--json") {
+        Format::Json
+    } else {
+        Format::Table
+    };
+    let kerned = switches.contains(&"--kerned");
+
+    let input = read_input(6);
+    let races = if kerned {
+        vec![race(&input)?]
+    } else {
+        races(&input)?
+    };
+    print!("{}", report::report(&boat, &races, format));
+    Ok(())
 }
 
 pub fn answer1() {
@@ -96,6 +410,15 @@ pub fn answer2() {
     println!("day06 part2: {}", solve2(&input));
 }
 
+#[cfg(test)]
+fn example() -> Vec<Race> {
+    races(
+        "Time:      7  15   30
+        Distance:  9  40  200",
+    )
+    .unwrap()
+}
+
 #[test]
 fn test1() {
     let input = "Time:      7  15   30
@@ -109,3 +432,188 @@ fn test2() {
         Distance:  9  40  200";
     assert_eq!(71503, solve2(input));
 }
+
+#[test]
+fn test3() {
+    let boat = Boat::default();
+    for lasts in 0..60 {
+        for distance in 0..lasts * lasts / 4 + 3 {
+            let race = Race { lasts, distance };
+            assert_eq!(boat.simulated(&race), boat.ways_to_win(&race), "{race:?}");
+        }
+    }
+
+    // 5 * 5 = 25 only ties
+    assert_eq!(
+        0,
+        boat.ways_to_win(&Race {
+            lasts: 10,
+            distance: 25
+        })
+    );
+    assert_eq!(
+        1,
+        boat.ways_to_win(&Race {
+            lasts: 10,
+            distance: 24
+        })
+    );
+}
+
+#[test]
+fn test4() {
+    for initial_speed in 0..4 {
+        for acceleration in 0..4 {
+            for max_speed in [None, Some(0), Some(2), Some(5), Some(11)] {
+                for delay in 0..4 {
+                    let boat = Boat {
+                        initial_speed,
+                        acceleration,
+                        max_speed,
+                        delay,
+                    };
+                    for lasts in 0..25 {
+                        for distance in (0..120).step_by(3) {
+                            let race = Race { lasts, distance };
+                            assert_eq!(
+                                boat.simulated(&race),
+                                boat.ways_to_win(&race),
+                                "{boat:?} {race:?}"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test5() {
+    let boat = Boat::from_str(
+        "# a slower boat
+        initial_speed = 2
+        acceleration = 3
+        max_speed = 20
+        delay = 1",
+    )
+    .unwrap();
+    assert_eq!(
+        Boat {
+            initial_speed: 2,
+            acceleration: 3,
+            max_speed: Some(20),
+            delay: 1
+        },
+        boat
+    );
+
+    let args: Vec<String> = ["--acceleration", "2", "--delay", "3"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let (boat, _) = Boat::from_args(&args, &[]).unwrap();
+    assert_eq!(
+        (2, 3, 0),
+        (boat.acceleration, boat.delay, boat.initial_speed)
+    );
+    // switches mix in with the settings
+    let args = ["--delay", "1", "--simulate"].map(String::from);
+    let (boat, switches) = Boat::from_args(&args, &["--simulate"]).unwrap();
+    assert_eq!((1, vec!["--simulate"]), (boat.delay, switches));
+    assert!(Boat::from_args(&args, &[]).is_err());
+    let args = ["--kerned", "--delay", "1", "--json"].map(String::from);
+    let (_, switches) = Boat::from_args(&args, &["--json", "--kerned"]).unwrap();
+    assert_eq!(vec!["--kerned", "--json"], switches);
+
+    assert_eq!(
+        "line 2: unknown boat setting drag",
+        Boat::from_str("delay = 1\ndrag = 2")
+            .unwrap_err()
+            .to_string()
+    );
+}
+
+#[test]
+fn test6() {
+    // the whole u64 range, where the discriminant alone needs far more than 128 bits
+    let boat = Boat::default();
+    let race = Race {
+        lasts: u64::MAX,
+        distance: u64::MAX,
+    };
+    // holding for 0, 1, lasts - 1 or lasts ms is at best a tie
+    assert_eq!(u64::MAX as u128 - 3, boat.ways_to_win(&race));
+
+    let fast = Boat {
+        acceleration: u64::MAX,
+        ..Boat::default()
+    };
+    assert!(fast.simulate(&race, 1 << 32));
+    // only holding for 0 or lasts ms loses
+    assert_eq!(u64::MAX as u128 - 1, fast.ways_to_win(&race));
+
+    let error = race_error("Time: 7 18446744073709551616\nDistance: 9 40");
+    assert_eq!(
+        "race time 718446744073709551616 is too large, the most supported is 18446744073709551615",
+        error
+    );
+    assert_eq!(
+        "record distance \"94x0\" is not a number",
+        race_error("Time: 7 15\nDistance: 9 4x0")
+    );
+    assert_eq!(
+        "2 race times but 1 record distances",
+        races("Time: 7 15\nDistance: 9").unwrap_err().to_string()
+    );
+}
+
+#[cfg(test)]
+fn race_error(s: &str) -> String {
+    race(s).unwrap_err().to_string()
+}
+
+#[test]
+fn test7() {
+    for boat in [
+        Boat::default(),
+        Boat {
+            initial_speed: 3,
+            acceleration: 2,
+            max_speed: Some(9),
+            delay: 2,
+        },
+        Boat {
+            initial_speed: 4,
+            acceleration: 1,
+            max_speed: None,
+            delay: 6,
+        },
+        Boat {
+            initial_speed: 2,
+            acceleration: 0,
+            max_speed: Some(1),
+            delay: 1,
+        },
+    ] {
+        for lasts in 0..30 {
+            for distance in (0..100).step_by(7) {
+                let race = Race { lasts, distance };
+
+                let winning: Vec<u64> = boat.winning(&race).into_iter().flatten().collect();
+                let simulated: Vec<u64> =
+                    (0..=lasts).filter(|h| boat.simulate(&race, *h)).collect();
+                assert_eq!(simulated, winning, "{boat:?} {race:?}");
+
+                let best = (0..=lasts)
+                    .max_by(|a, b| {
+                        boat.distance(&race, *a)
+                            .cmp(&boat.distance(&race, *b))
+                            .then(b.cmp(a))
+                    })
+                    .unwrap();
+                assert_eq!(best, boat.best_hold(&race), "{boat:?} {race:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::ops::RangeInclusive;
+
+use num_bigint::{BigInt, BigUint};
+
+use super::{Boat, Race};
+use crate::util::{self, Align};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    race: usize,
+    lasts: u64,
+    record: u64,
+    winning: Vec<RangeInclusive<u64>>,
+    ways: u128,
+    best_hold: u64,
+    best_distance: BigUint,
+    // negative when even the best hold time can't beat the record
+    margin: BigInt,
+}
+
+fn line(boat: &Boat, race: &Race, number: usize) -> Line {
+    let best_hold = boat.best_hold(race);
+    let best_distance = boat.distance(race, best_hold);
+    Line {
+        race: number,
+        lasts: race.lasts,
+        record: race.distance,
+        winning: boat.winning(race),
+        ways: boat.ways_to_win(race),
+        best_hold,
+        margin: BigInt::from(best_distance.clone()) - BigInt::from(race.distance),
+        best_distance,
+    }
+}
+
+fn table(lines: &[Line]) -> String {
+    let header = [
+        "race", "time", "record", "winning", "ways", "best", "distance", "margin",
+    ];
+    let rows: Vec<[String; 8]> = lines
+        .iter()
+        .map(|line| {
+            let winning: Vec<String> = line
+                .winning
+                .iter()
+                .map(|w| format!("{}..={}", w.start(), w.end()))
+                .collect();
+            [
+                line.race.to_string(),
+                line.lasts.to_string(),
+                line.record.to_string(),
+                if winning.is_empty() {
+                    "-".to_string()
+                } else {
+                    winning.join(", ")
+                },
+                line.ways.to_string(),
+                line.best_hold.to_string(),
+                line.best_distance.to_string(),
+                line.margin.to_string(),
+            ]
+        })
+        .collect();
+
+    util::table(header, &rows, Align::Right)
+}
+
+fn json(lines: &[Line]) -> String {
+    let lines: Vec<String> = lines
+        .iter()
+        .map(|line| {
+            let winning: Vec<String> = line
+                .winning
+                .iter()
+                .map(|w| format!("[{}, {}]", w.start(), w.end()))
+                .collect();
+            format!(
+                "  {{\"race\": {}, \"time\": {}, \"record\": {}, \"winning\": [{}], \"ways\": {}, \
+                 \"best_hold\": {}, \"best_distance\": {}, \"margin\": {}}}",
+                line.race,
+                line.lasts,
+                line.record,
+                winning.join(", "),
+                line.ways,
+                line.best_hold,
+                line.best_distance,
+                line.margin
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", lines.join(",\n"))
+}
+
+pub fn report(boat: &Boat, races: &[Race], format: Format) -> String {
+    let lines: Vec<Line> = races
+        .iter()
+        .enumerate()
+        .map(|(i, race)| line(boat, race, i + 1))
+        .collect();
+    match format {
+        Format::Table => table(&lines),
+        Format::Json => json(&lines),
+    }
+}
+
+#[test]
+fn test1() {
+    let races = super::example();
+    let lines: Vec<Line> = races
+        .iter()
+        .enumerate()
+        .map(|(i, race)| line(&Boat::default(), race, i + 1))
+        .collect();
+
+    assert_eq!(vec![2..=5], lines[0].winning);
+    assert_eq!(3, lines[0].best_hold);
+    assert_eq!(BigUint::from(12u32), lines[0].best_distance);
+    assert_eq!(BigInt::from(3), lines[0].margin);
+
+    assert_eq!(vec![11..=19], lines[2].winning);
+    assert_eq!(9, lines[2].ways);
+    assert_eq!(15, lines[2].best_hold);
+    assert_eq!(BigInt::from(25), lines[2].margin);
+
+    let lost = line(
+        &Boat::default(),
+        &Race {
+            lasts: 10,
+            distance: 30,
+        },
+        1,
+    );
+    assert!(lost.winning.is_empty());
+    assert_eq!(BigInt::from(-5), lost.margin);
+}
+
+#[test]
+fn test2() {
+    let races = super::example();
+
+    let table = report(&Boat::default(), &races, Format::Table);
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(4, lines.len());
+    assert!(lines[0].trim_start().starts_with("race  time  record"));
+    assert!(lines[1].contains("2..=5"));
+
+    let json = report(&Boat::default(), &races, Format::Json);
+    assert!(
+        json.starts_with("[\n  {\"race\": 1, \"time\": 7, \"record\": 9, \"winning\": [[2, 5]]")
+    );
+    assert!(json.contains("\"best_hold\": 15, \"best_distance\": 225, \"margin\": 25}\n]"));
+}
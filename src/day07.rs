@@ -1,250 +1,433 @@
-use std::{cmp::Ordering, collections::HashMap, str::FromStr};
+use std::{collections::HashMap, str::FromStr};
+
+use anyhow::{anyhow, bail};
 
 use crate::util::read_input;
 
-#[derive(PartialEq, PartialOrd, Ord, Eq, Debug)]
-enum CardValueWithJoker {
-    J,
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    T,
-    Q,
-    K,
-    A,
+mod explain;
+
+// how many of each card, most first: [3, 2] is a full house
+#[derive(Debug, Clone, PartialEq)]
+struct Category {
+    name: String,
+    signature: Vec<usize>,
 }
 
-impl From<char> for CardValueWithJoker {
-    fn from(value: char) -> Self {
-        match value {
-            'J' => CardValueWithJoker::J,
-            '2' => CardValueWithJoker::Two,
-            '3' => CardValueWithJoker::Three,
-            '4' => CardValueWithJoker::Four,
-            '5' => CardValueWithJoker::Five,
-            '6' => CardValueWithJoker::Six,
-            '7' => CardValueWithJoker::Seven,
-            '8' => CardValueWithJoker::Eight,
-            '9' => CardValueWithJoker::Nine,
-            'T' => CardValueWithJoker::T,
-            'Q' => CardValueWithJoker::Q,
-            'K' => CardValueWithJoker::K,
-            'A' => CardValueWithJoker::A,
-            _ => unreachable!(),
+impl FromStr for Category {
+    type Err = anyhow::Error;
+
+    // `name=3,2`
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let Some((name, signature)) = s.split_once('=') else {
+            bail!("expected <name>=<counts>, got {s:?}");
+        };
+        let mut signature = signature
+            .split(',')
+            .map(|n| n.trim().parse())
+            .collect::<Result<Vec<usize>, _>>()
+            .map_err(|_| anyhow!("bad counts in category {s:?}"))?;
+        if signature.contains(&0) {
+            bail!("category {name} counts a card zero times");
         }
+        signature.sort_by(|a, b| b.cmp(a));
+        Ok(Category {
+            name: name.trim().to_string(),
+            signature,
+        })
     }
 }
 
-#[derive(PartialEq, PartialOrd, Ord, Eq, Debug)]
-enum CardValue {
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-    Seven,
-    Eight,
-    Nine,
-    T,
-    J,
-    Q,
-    K,
-    A,
+// card order, wild cards and categories, which are what variants change
+#[derive(Debug, Clone, PartialEq)]
+struct Rules {
+    // weakest card first
+    order: Vec<char>,
+    wild: Vec<char>,
+    // weakest category first
+    categories: Vec<Category>,
 }
 
-impl From<char> for CardValue {
-    fn from(value: char) -> Self {
-        match value {
-            '2' => CardValue::Two,
-            '3' => CardValue::Three,
-            '4' => CardValue::Four,
-            '5' => CardValue::Five,
-            '6' => CardValue::Six,
-            '7' => CardValue::Seven,
-            '8' => CardValue::Eight,
-            '9' => CardValue::Nine,
-            'T' => CardValue::T,
-            'J' => CardValue::J,
-            'Q' => CardValue::Q,
-            'K' => CardValue::K,
-            'A' => CardValue::A,
-            _ => unreachable!(),
+const FIVE_CARD_CATEGORIES: &str = "high card=1,1,1,1,1; one pair=2,1,1,1; two pair=2,2,1; \
+    three of a kind=3,1,1; full house=3,2; four of a kind=4,1; five of a kind=5";
+
+impl Rules {
+    fn new(order: &str, wild: &str, table: &str) -> anyhow::Result<Self> {
+        let order: Vec<char> = order.chars().collect();
+        for (i, c) in order.iter().enumerate() {
+            if order[..i].contains(c) {
+                bail!("{c} is ranked twice");
+            }
+        }
+        let wild: Vec<char> = wild.chars().collect();
+        if let Some(c) = wild.iter().find(|c| !order.contains(c)) {
+            bail!("wild card {c} is not ranked");
         }
-    }
-}
 
-#[derive(PartialEq, PartialOrd, Ord, Eq, Debug, Clone)]
-enum HandType {
-    Fives,
-    Fours,
-    FullHouse,
-    Threes,
-    TwoPair,
-    OnePair,
-    HighCard,
-}
+        let categories = table
+            .split(';')
+            .filter(|s| !s.trim().is_empty())
+            .map(Category::from_str)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        for (i, category) in categories.iter().enumerate() {
+            if let Some(other) = categories[..i]
+                .iter()
+                .find(|c| c.signature == category.signature)
+            {
+                bail!(
+                    "{} and {} are the same category {:?}",
+                    other.name,
+                    category.name,
+                    category.signature
+                );
+            }
+        }
 
-impl HandType {
-    fn from_hash_map(hash_map: &HashMap<char, i32>) -> Self {
-        match hash_map.len() {
-            1 => HandType::Fives,
-            2 if hash_map.iter().any(|(_, &v)| v == 4) => HandType::Fours,
-            2 => HandType::FullHouse,
-            3 if hash_map.iter().any(|(_, &v)| v == 3) => HandType::Threes,
-            3 => HandType::TwoPair,
-            4 => HandType::OnePair,
-            5 => HandType::HighCard,
-            _ => unreachable!("Cant create HandType from HashMap {:?}", hash_map),
+        let size = |category: &Category| category.signature.iter().sum::<usize>();
+        if let Some(category) = categories.iter().find(|c| size(c) != size(&categories[0])) {
+            bail!(
+                "{} has {} cards but {} has {}",
+                category.name,
+                size(category),
+                categories[0].name,
+                size(&categories[0])
+            );
         }
+
+        Ok(Rules {
+            order,
+            wild,
+            categories,
+        })
     }
 
-    fn from_str_with_joker(s: &str) -> Self {
-        let mut hash_map = HashMap::new();
-        let mut joker_count = 0;
+    // part 1
+    fn standard() -> Self {
+        Rules::new("23456789TJQKA", "", FIVE_CARD_CATEGORIES).unwrap()
+    }
 
-        for c in s.chars() {
-            if c == 'J' {
-                joker_count += 1;
-            } else {
-                hash_map.entry(c).and_modify(|e| *e += 1).or_insert(1);
+    // part 2, jacks are jokers and rank lowest
+    fn jokers() -> Self {
+        Rules::new("J23456789TQKA", "J", FIVE_CARD_CATEGORIES).unwrap()
+    }
+
+    // `--order`, `--wild` and `--categories` on top of the standard rules, or `--jokers`
+    fn from_args(args: &[String]) -> anyhow::Result<Self> {
+        let standard = Rules::standard();
+        let mut order: String = standard.order.iter().collect();
+        let mut wild = String::new();
+        let mut categories = FIVE_CARD_CATEGORIES.to_string();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--jokers" {
+                let jokers = Rules::jokers();
+                order = jokers.order.iter().collect();
+                wild = jokers.wild.iter().collect();
+                continue;
+            }
+            let Some(value) = args.next() else {
+                bail!("{arg} needs a value");
+            };
+            match arg.as_str() {
+                "--order" => order = value.clone(),
+                "--wild" => wild = value.clone(),
+                "--categories" => categories = value.clone(),
+                _ => bail!("unknown rules option {arg}"),
             }
         }
+        Rules::new(&order, &wild, &categories)
+    }
+
+    // how many cards the categories are made of, every one has to agree
+    fn hand_size(&self) -> Option<usize> {
+        self.categories
+            .first()
+            .map(|category| category.signature.iter().sum())
+    }
+
+    fn rank(&self, card: char) -> usize {
+        self.order.iter().position(|c| *c == card).unwrap()
+    }
+
+    // how many of each card, most first
+    fn signature(cards: &str) -> Vec<usize> {
+        let mut counts: HashMap<char, usize> = HashMap::new();
+        for c in cards.chars() {
+            *counts.entry(c).or_default() += 1;
+        }
+        let mut signature: Vec<usize> = counts.into_values().collect();
+        signature.sort_by(|a, b| b.cmp(a));
+        signature
+    }
+
+    // index into `categories`, so it doubles as the category's strength
+    fn category(&self, cards: &str) -> Option<usize> {
+        let signature = Rules::signature(cards);
+        self.categories
+            .iter()
+            .position(|category| category.signature == signature)
+    }
 
-        if hash_map.len() == 0 {
-            return HandType::Fives;
+    // tries every split of the wild cards between held cards and new ones rather than every
+    // card for every wild card, ties go to the highest ranked cards
+    fn resolve(&self, cards: &str) -> anyhow::Result<Resolution> {
+        let wild_count = cards.chars().filter(|c| self.wild.contains(c)).count();
+        if wild_count == 0 {
+            let Some(category) = self.category(cards) else {
+                bail!(
+                    "{cards} has counts {:?}, which is no category",
+                    Rules::signature(cards)
+                );
+            };
+            return Ok(Resolution {
+                category,
+                cards: cards.to_string(),
+            });
         }
 
-        let highest_char = hash_map
+        let mut held: Vec<char> = vec![];
+        for c in cards.chars().filter(|c| !self.wild.contains(c)) {
+            if !held.contains(&c) {
+                held.push(c);
+            }
+        }
+        let fresh = self
+            .order
             .iter()
-            .max_by(|a, b| a.1.cmp(&b.1))
-            .map(|(k, _)| k)
-            .unwrap();
+            .rev()
+            .filter(|c| !self.wild.contains(c) && !held.contains(c))
+            .take(wild_count)
+            .copied();
+        let targets: Vec<char> = held.iter().copied().chain(fresh).collect();
+        if targets.is_empty() {
+            bail!("{cards} is all wild cards and there is nothing for them to be");
+        }
 
-        hash_map
-            .entry(*highest_char)
-            .and_modify(|e| *e += joker_count);
+        // every wild card picks a target, in non-decreasing order so each split comes up once
+        let mut best: Option<Resolution> = None;
+        let mut picks = vec![0; wild_count];
+        loop {
+            // new cards have to be used in order, otherwise the same split comes up again
+            let new: Vec<usize> = picks
+                .iter()
+                .filter(|p| **p >= held.len())
+                .copied()
+                .collect();
+            let in_order = new
+                .iter()
+                .max()
+                .is_none_or(|max| (held.len()..=*max).all(|target| new.contains(&target)));
+
+            if in_order {
+                let mut picked = picks.iter().map(|p| targets[*p]);
+                let substituted: String = cards
+                    .chars()
+                    .map(|c| {
+                        if self.wild.contains(&c) {
+                            picked.next().unwrap()
+                        } else {
+                            c
+                        }
+                    })
+                    .collect();
+                if let Some(category) = self.category(&substituted) {
+                    let resolution = Resolution {
+                        category,
+                        cards: substituted,
+                    };
+                    if best
+                        .as_ref()
+                        .is_none_or(|best| self.beats(&resolution, best))
+                    {
+                        best = Some(resolution);
+                    }
+                }
+            }
+
+            // next non-decreasing sequence
+            let Some(i) = picks.iter().rposition(|p| p + 1 < targets.len()) else {
+                break;
+            };
+            let next = picks[i] + 1;
+            picks[i..].fill(next);
+        }
 
-        HandType::from_hash_map(&hash_map)
+        match best {
+            Some(best) => Ok(best),
+            None => bail!("no way of playing the wild cards in {cards} makes a category"),
+        }
     }
-}
 
-impl FromStr for HandType {
-    type Err = anyhow::Error;
+    fn beats(&self, a: &Resolution, b: &Resolution) -> bool {
+        let ranks = |cards: &str| -> Vec<usize> { cards.chars().map(|c| self.rank(c)).collect() };
+        (a.category, ranks(&a.cards)) > (b.category, ranks(&b.cards))
+    }
 
-    fn from_str(s: &str) -> anyhow::Result<Self> {
-        let mut hash_map = HashMap::new();
-        for c in s.chars() {
-            hash_map.entry(c).and_modify(|e| *e += 1).or_insert(1);
+    // every card for every wild card, to test `resolve`
+    #[cfg(test)]
+    fn resolve_brute_force(&self, cards: &str) -> Option<usize> {
+        let playable: Vec<char> = self
+            .order
+            .iter()
+            .filter(|c| !self.wild.contains(c))
+            .copied()
+            .collect();
+        let mut hands = vec![String::new()];
+        for c in cards.chars() {
+            let options = if self.wild.contains(&c) {
+                playable.clone()
+            } else {
+                vec![c]
+            };
+            hands = hands
+                .iter()
+                .flat_map(|hand| options.iter().map(move |o| format!("{hand}{o}")))
+                .collect();
         }
+        hands.iter().filter_map(|hand| self.category(hand)).max()
+    }
 
-        let hand_type = HandType::from_hash_map(&hash_map);
-        Ok(hand_type)
+    // category then ranks, as digits in base `order.len()`
+    fn sort_key(&self, category: usize, cards: &str) -> anyhow::Result<u64> {
+        let base = self.order.len() as u64;
+        cards.chars().try_fold(category as u64, |key, card| {
+            key.checked_mul(base)
+                .and_then(|key| key.checked_add(self.rank(card) as u64))
+                .ok_or_else(|| anyhow!("{cards} has too many cards to fit a sort key"))
+        })
     }
+
+    // the ordering the key encodes
+    #[cfg(test)]
+    fn compare(&self, a: &Hand, b: &Hand) -> std::cmp::Ordering {
+        a.category.cmp(&b.category).then_with(|| {
+            a.cards
+                .chars()
+                .zip(b.cards.chars())
+                .map(|(a, b)| self.rank(a).cmp(&self.rank(b)))
+                .find(|ord| ord.is_ne())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+    }
+}
+
+// the strongest category a hand can make and the cards its wild cards were played as
+#[derive(Debug, Clone, PartialEq)]
+struct Resolution {
+    category: usize,
+    cards: String,
 }
 
 #[derive(Debug)]
 struct Hand {
     cards: String,
-    hand_type: HandType,
+    // the cards with the wild cards played
+    played: String,
+    category: usize,
     bid: usize,
+    key: u64,
 }
 
 impl Hand {
-    fn from_str_with_joker(s: &str) -> anyhow::Result<Self> {
-        let (cards, bid) = s.split_once(" ").unwrap();
-        let hand_type = HandType::from_str_with_joker(cards);
+    fn parse(s: &str, rules: &Rules) -> anyhow::Result<Self> {
+        let Some((cards, bid)) = s.split_once(char::is_whitespace) else {
+            bail!("{s:?} has no bid");
+        };
+        let bid = bid.trim();
+        if let Some(c) = cards.chars().find(|c| !rules.order.contains(c)) {
+            bail!("{c} in {cards} is not a card");
+        }
+        if let Some(size) = rules.hand_size() {
+            if cards.chars().count() != size {
+                bail!(
+                    "{cards} has {} cards, a hand has {size}",
+                    cards.chars().count()
+                );
+            }
+        }
+        let bid = match bid.parse() {
+            Ok(bid) => bid,
+            Err(_) if bid.starts_with('-') && bid[1..].parse::<usize>().is_ok() => {
+                bail!("bid {bid} for {cards} is negative")
+            }
+            Err(_) => bail!("bid {bid:?} for {cards} is not a number"),
+        };
+        let Resolution {
+            category,
+            cards: played,
+        } = rules.resolve(cards)?;
+        let key = rules.sort_key(category, cards)?;
         let cards = cards.to_string();
-        let bid = bid.parse().unwrap();
 
         Ok(Hand {
             cards,
-            hand_type,
+            played,
+            category,
             bid,
+            key,
         })
     }
+}
 
-    fn compare_equal_hands(&self, other_hand: &Hand, index: usize, with_joker: bool) -> Ordering {
-        let a_char = self.cards.chars().nth(index).unwrap();
-        let b_char = other_hand.cards.chars().nth(index).unwrap();
-
-        if with_joker {
-            let a = CardValueWithJoker::from(a_char);
-            let b = CardValueWithJoker::from(b_char);
-            a.partial_cmp(&b).unwrap()
-        } else {
-            let a = CardValue::from(a_char);
-            let b = CardValue::from(b_char);
-            a.partial_cmp(&b).unwrap()
+// weakest hand first
+fn ranked(s: &str, rules: &Rules) -> anyhow::Result<Vec<Hand>> {
+    let mut hands: Vec<Hand> = vec![];
+    // cards -> line they were first dealt on
+    let mut dealt: HashMap<String, usize> = HashMap::new();
+    for (i, line) in s.lines().enumerate() {
+        let number = i + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let hand = Hand::parse(line, rules).map_err(|e| anyhow!("line {number}: {e}"))?;
+        if let Some(first) = dealt.insert(hand.cards.clone(), number) {
+            bail!(
+                "line {number}: {} was already dealt on line {first}",
+                hand.cards
+            );
         }
+        hands.push(hand);
     }
-}
-
-impl FromStr for Hand {
-    type Err = anyhow::Error;
 
-    fn from_str(s: &str) -> anyhow::Result<Self> {
-        let (hand, bid) = s.split_once(" ").unwrap();
-        let hand_type = HandType::from_str(hand).unwrap();
-        let hand = hand.to_string();
-        let bid = bid.parse().unwrap();
-
-        Ok(Hand {
-            cards: hand,
-            hand_type,
-            bid,
-        })
-    }
+    hands.sort_unstable_by_key(|hand| hand.key);
+    Ok(hands)
 }
 
-fn solve2(s: &str) -> usize {
-    let mut hands: Vec<Hand> = s
-        .lines()
-        .map(|l| Hand::from_str_with_joker(l.trim()).unwrap())
-        .collect();
-
-    hands.sort_by(|a, b| {
-        let mut ord = b.hand_type.partial_cmp(&a.hand_type).unwrap();
-        let mut index = 0;
-        while let Ordering::Equal = ord {
-            ord = a.compare_equal_hands(b, index, true);
-            index += 1;
-        }
-        ord
-    });
-
-    hands
+fn solve(s: &str, rules: &Rules) -> anyhow::Result<usize> {
+    Ok(ranked(s, rules)?
         .iter()
         .enumerate()
         .map(|(rank, hand)| hand.bid * (rank + 1))
-        .sum()
+        .sum())
 }
 
 fn solve1(s: &str) -> usize {
-    let mut hands: Vec<Hand> = s
-        .lines()
-        .map(|l| Hand::from_str(l.trim()).unwrap())
-        .collect();
+    solve(s, &Rules::standard()).unwrap()
+}
 
-    hands.sort_by(|a, b| {
-        let mut ord = b.hand_type.partial_cmp(&a.hand_type).unwrap();
-        let mut index = 0;
-        while let Ordering::Equal = ord {
-            ord = a.compare_equal_hands(b, index, false);
-            index += 1;
-        }
-        ord
-    });
+fn solve2(s: &str) -> usize {
+    solve(s, &Rules::jokers()).unwrap()
+}
 
-    hands
-        .iter()
-        .enumerate()
-        .map(|(rank, hand)| hand.bid * (rank + 1))
-        .sum()
+pub fn rules(args: &[String]) -> anyhow::Result<()> {
+    let rules = Rules::from_args(args)?;
+    let input = read_input(7);
+    let hands = ranked(&input, &rules)?;
+    if let Some(best) = hands.last() {
+        println!(
+            "day07 strongest hand: {} played as {} ({})",
+            best.cards, best.played, rules.categories[best.category].name
+        );
+    }
+    println!("day07 winnings: {}", solve(&input, &rules)?);
+    Ok(())
+}
+
+pub fn explain(args: &[String]) -> anyhow::Result<()> {
+    let rules = Rules::from_args(args)?;
+    let input = read_input(7);
+    print!("{}", explain::explain(&rules, &ranked(&input, &rules)?));
+    Ok(())
 }
 
 pub fn answer1() {
@@ -257,6 +440,13 @@ pub fn answer2() {
     println!("day07 part2: {}", solve2(&input));
 }
 
+#[cfg(test)]
+const EXAMPLE: &str = "32T3K 765
+    T55J5 684
+    KK677 28
+    KTJJT 220
+    QQQJA 483";
+
 #[test]
 fn test1() {
     let input = "32T3K 765
@@ -278,3 +468,224 @@ fn test2() {
 
     assert_eq!(5905, solve2(input));
 }
+
+#[test]
+fn test3() {
+    // the same engine with both wild cards
+    let rules = Rules::new("J23456789TQKA", "JT", FIVE_CARD_CATEGORIES).unwrap();
+    let category = |cards| &rules.categories[rules.resolve(cards).unwrap().category].name;
+    assert_eq!("three of a kind", category("32T3K"));
+    assert_eq!("five of a kind", category("KTJJT"));
+    // T55J5 and KTJJT are both five of a kind now, and T ranks below K
+    assert_eq!(
+        28 + 765 * 2 + 483 * 3 + 684 * 4 + 220 * 5,
+        solve(EXAMPLE, &rules).unwrap()
+    );
+
+    let args = |args: &[&str]| -> Vec<String> { args.iter().map(|s| s.to_string()).collect() };
+    assert_eq!(
+        Rules::jokers(),
+        Rules::from_args(&args(&["--jokers"])).unwrap()
+    );
+    let rules = Rules::from_args(&args(&["--jokers", "--wild", "JT"])).unwrap();
+    assert_eq!(vec!['J', 'T'], rules.wild);
+    assert!(Rules::from_args(&args(&["--wild"])).is_err());
+
+    assert!(Rules::new("23456789TJQKAA", "", FIVE_CARD_CATEGORIES).is_err());
+    assert!(Rules::new("23456789TJQKA", "*", FIVE_CARD_CATEGORIES).is_err());
+}
+
+#[cfg(test)]
+fn generate(hands: usize, seed: u64) -> String {
+    let mut next = crate::util::xorshift(seed);
+
+    let cards: Vec<char> = "23456789TJQKA".chars().collect();
+    let mut s = String::new();
+    for _ in 0..hands {
+        // a small deck now and then, so the stronger hands turn up too
+        let deck = if next().is_multiple_of(4) { 3 } else { 13 };
+        for _ in 0..5 {
+            s.push(cards[(next() % deck) as usize]);
+        }
+        s.push_str(&format!(" {}\n", 1 + next() % 1000));
+    }
+    s
+}
+
+#[test]
+fn test4() {
+    let input = generate(2000, 7);
+    for rules in [Rules::standard(), Rules::jokers()] {
+        let hands: Vec<Hand> = input
+            .lines()
+            .map(|l| Hand::parse(l, &rules).unwrap())
+            .collect();
+        for pair in hands.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            assert_eq!(rules.compare(a, b), a.key.cmp(&b.key), "{a:?} {b:?}");
+        }
+    }
+
+    // 36 ranks, so 11 cards still fit in 64 bits but 12 don't
+    let long = Rules::new("0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ", "", "").unwrap();
+    assert!(long.sort_key(6, "ZZZZZZZZZZZ").is_ok());
+    let error = long.sort_key(6, "0123456789AB").unwrap_err();
+    assert_eq!(
+        "0123456789AB has too many cards to fit a sort key",
+        error.to_string()
+    );
+}
+
+#[test]
+#[ignore]
+fn bench() {
+    use std::time::Instant;
+
+    let input = generate(1_000_000, 42);
+    let rules = Rules::jokers();
+
+    let start = Instant::now();
+    let mut hands: Vec<Hand> = input
+        .lines()
+        .map(|l| Hand::parse(l, &rules).unwrap())
+        .collect();
+    let parse_time = start.elapsed();
+
+    let mut compared: Vec<usize> = (0..hands.len()).collect();
+    let start = Instant::now();
+    compared.sort_by(|a, b| rules.compare(&hands[*a], &hands[*b]));
+    let compare_time = start.elapsed();
+
+    let start = Instant::now();
+    hands.sort_unstable_by_key(|hand| hand.key);
+    let key_time = start.elapsed();
+
+    println!(
+        "1M hands: parse {parse_time:?}, sort by comparator {compare_time:?}, sort by key {key_time:?}"
+    );
+}
+
+#[test]
+fn test5() {
+    let rules = Rules::new(
+        "23456789TJQKA",
+        "J",
+        "high card=1,1,1; pair=1,2; three of a kind=3",
+    )
+    .unwrap();
+    assert_eq!(vec![2, 1], rules.categories[1].signature);
+
+    let input = "A23 10
+        JJJ 1
+        KK2 100
+        2J2 1000";
+    // A23 < KK2 < 2J2 < JJJ, a wild J still ranks where the order puts it
+    assert_eq!(10 + 100 * 2 + 1000 * 3 + 4, solve(input, &rules).unwrap());
+
+    let error = Hand::parse("A2345 1", &rules).unwrap_err();
+    assert_eq!("A2345 has 5 cards, a hand has 3", error.to_string());
+    let pairs = Rules::new("23456789TJQKA", "", "pair=2,1; three of a kind=3").unwrap();
+    let error = Hand::parse("A23 1", &pairs).unwrap_err();
+    assert_eq!(
+        "A23 has counts [1, 1, 1], which is no category",
+        error.to_string()
+    );
+    // a six card hand doesn't fit the standard table either
+    assert!(Hand::parse("AAKKQQ 1", &Rules::standard()).is_err());
+
+    let error = Rules::new("23", "", "pair=2; doubles=2").unwrap_err();
+    assert_eq!(
+        "pair and doubles are the same category [2]",
+        error.to_string()
+    );
+}
+
+#[test]
+fn test6() {
+    let rules = Rules::jokers();
+    assert_eq!(
+        Resolution {
+            category: 5,
+            cards: "KTTTT".to_string()
+        },
+        rules.resolve("KTJJT").unwrap()
+    );
+    // with nothing else to join the jokers become the best card there is
+    assert_eq!("AAAAA", rules.resolve("JJJJJ").unwrap().cards);
+    // two pairs, the joker joins the higher one
+    assert_eq!("KKQQK", rules.resolve("KKQQJ").unwrap().cards);
+
+    // two pair beats three of a kind here, so the jokers shouldn't all pile onto one card
+    let odd = Rules::new(
+        "J23456789TQKA",
+        "J",
+        "high card=1,1,1,1,1; one pair=2,1,1,1; three of a kind=3,1,1; two pair=2,2,1; \
+         full house=3,2; four of a kind=4,1",
+    )
+    .unwrap();
+    let resolution = odd.resolve("2J3J4").unwrap();
+    assert_eq!("two pair", odd.categories[resolution.category].name);
+    assert_eq!("23344", resolution.cards);
+    // five of a kind isn't a category, so JJJJJ can at best be four of a kind
+    assert_eq!(
+        "four of a kind",
+        odd.categories[odd.resolve("JJJJJ").unwrap().category].name
+    );
+
+    for rules in [
+        Rules::jokers(),
+        odd,
+        Rules::new("J23456789TQKA", "JQ", FIVE_CARD_CATEGORIES).unwrap(),
+    ] {
+        for line in generate(300, 11).lines() {
+            let (cards, _) = line.split_once(' ').unwrap();
+            let resolution = rules.resolve(cards);
+            assert_eq!(
+                rules.resolve_brute_force(cards),
+                resolution.as_ref().ok().map(|r| r.category),
+                "{cards}"
+            );
+            // without five of a kind some hands have no category at all
+            let Ok(resolution) = resolution else {
+                continue;
+            };
+            assert_eq!(rules.category(&resolution.cards), Some(resolution.category));
+            for (a, b) in cards.chars().zip(resolution.cards.chars()) {
+                assert!(
+                    a == b || rules.wild.contains(&a),
+                    "{cards} {}",
+                    resolution.cards
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test7() {
+    let rules = Rules::standard();
+    let error = |input: &str| ranked(input, &rules).unwrap_err().to_string();
+
+    // blank lines are skipped but still counted
+    assert_eq!(
+        "line 3: X in 32X3K is not a card",
+        error("KK677 28\n\n32X3K 765")
+    );
+    assert_eq!("line 1: KK67 has 4 cards, a hand has 5", error("KK67 28"));
+    assert_eq!("line 2: \"KK677\" has no bid", error("32T3K 765\nKK677"));
+    assert_eq!("line 1: bid -28 for KK677 is negative", error("KK677 -28"));
+    assert_eq!(
+        "line 1: bid \"28x\" for KK677 is not a number",
+        error("KK677 28x")
+    );
+    assert_eq!(
+        "line 3: KK677 was already dealt on line 1",
+        error("KK677 28\n32T3K 765\nKK677 1")
+    );
+
+    let error = Rules::new("23", "", "pair=2; three of a kind=3").unwrap_err();
+    assert_eq!(
+        "three of a kind has 3 cards but pair has 2",
+        error.to_string()
+    );
+}
@@ -0,0 +1,160 @@
+use std::fmt::{self, Write};
+
+use super::{Hand, Rules};
+use crate::util::{self, Align};
+
+// what put a hand above the one ranked just below it
+#[derive(Debug, Clone, PartialEq)]
+enum Decided {
+    // the weakest hand has nothing below it
+    Lowest,
+    Category,
+    // position counted from 1, with the two cards compared there
+    Card(usize, char, char),
+    // the same cards, which `ranked` doesn't let through
+    Tie,
+}
+
+impl fmt::Display for Decided {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Decided::Lowest => write!(f, "-"),
+            Decided::Category => write!(f, "category"),
+            Decided::Card(position, stronger, weaker) => {
+                write!(f, "card {position} ({stronger} > {weaker})")
+            }
+            Decided::Tie => write!(f, "tie"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    rank: usize,
+    cards: String,
+    played: String,
+    // the category with the wild cards taken at face value, if that makes one
+    plain: Option<usize>,
+    category: usize,
+    bid: usize,
+    winnings: usize,
+    decided: Decided,
+}
+
+fn decided(rules: &Rules, weaker: &Hand, stronger: &Hand) -> Decided {
+    if weaker.category != stronger.category {
+        return Decided::Category;
+    }
+    stronger
+        .cards
+        .chars()
+        .zip(weaker.cards.chars())
+        .enumerate()
+        .find(|(_, (s, w))| rules.rank(*s) != rules.rank(*w))
+        .map_or(Decided::Tie, |(i, (s, w))| Decided::Card(i + 1, s, w))
+}
+
+fn lines(rules: &Rules, hands: &[Hand]) -> Vec<Line> {
+    hands
+        .iter()
+        .enumerate()
+        .map(|(i, hand)| Line {
+            rank: i + 1,
+            cards: hand.cards.clone(),
+            played: hand.played.clone(),
+            plain: rules.category(&hand.cards),
+            category: hand.category,
+            bid: hand.bid,
+            winnings: hand.bid * (i + 1),
+            decided: match i {
+                0 => Decided::Lowest,
+                _ => decided(rules, &hands[i - 1], hand),
+            },
+        })
+        .collect()
+}
+
+// weakest first, with what put each hand above the one below
+pub fn explain(rules: &Rules, hands: &[Hand]) -> String {
+    let lines = lines(rules, hands);
+    let name = |category: Option<usize>| {
+        category.map_or("-".to_string(), |c| rules.categories[c].name.clone())
+    };
+
+    let header = [
+        "rank",
+        "hand",
+        "played",
+        "type",
+        "wild type",
+        "bid",
+        "winnings",
+        "decided by",
+    ];
+    let rows: Vec<[String; 8]> = lines
+        .iter()
+        .map(|line| {
+            [
+                line.rank.to_string(),
+                line.cards.clone(),
+                line.played.clone(),
+                name(line.plain),
+                name(Some(line.category)),
+                line.bid.to_string(),
+                line.winnings.to_string(),
+                line.decided.to_string(),
+            ]
+        })
+        .collect();
+
+    let mut s = util::table(header, &rows, Align::Left);
+    let total: usize = lines.iter().map(|line| line.winnings).sum();
+    let _ = writeln!(s, "total winnings: {total}");
+    s
+}
+
+#[test]
+fn test1() {
+    let rules = Rules::jokers();
+    let standings = lines(&rules, &super::ranked(super::EXAMPLE, &rules).unwrap());
+
+    let cards: Vec<&str> = standings.iter().map(|line| line.cards.as_str()).collect();
+    assert_eq!(vec!["32T3K", "KK677", "T55J5", "QQQJA", "KTJJT"], cards);
+    assert_eq!(Decided::Lowest, standings[0].decided);
+    assert_eq!(Decided::Category, standings[1].decided);
+    // T55J5 and QQQJA are both four of a kind, Q beats T straight away
+    assert_eq!(Decided::Card(1, 'Q', 'T'), standings[3].decided);
+    assert_eq!(Decided::Card(1, 'K', 'Q'), standings[4].decided);
+
+    // KTJJT is only two pair until the jokers are played
+    assert_eq!(Some(2), standings[4].plain);
+    assert_eq!(5, standings[4].category);
+    assert_eq!("KTTTT", standings[4].played);
+    assert_eq!(1100, standings[4].winnings);
+
+    let rules = Rules::standard();
+    let hand = |s| Hand::parse(s, &rules).unwrap();
+    assert_eq!(
+        Decided::Card(4, 'J', '9'),
+        decided(&rules, &hand("AKQ9T 2"), &hand("AKQJT 1"))
+    );
+    // duplicate hands are turned away when parsing, but there'd be nothing to tell them apart
+    assert_eq!(
+        Decided::Tie,
+        decided(&rules, &hand("AKQJT 1"), &hand("AKQJT 3"))
+    );
+}
+
+#[test]
+fn test2() {
+    let rules = Rules::jokers();
+    let table = explain(&rules, &super::ranked(super::EXAMPLE, &rules).unwrap());
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(7, lines.len());
+    assert!(lines[0].starts_with("rank  hand   played  type"));
+    assert!(lines[1].ends_with("765       -"));
+    assert!(lines[4].contains("four of a kind"));
+    assert!(lines[4].ends_with("card 1 (Q > T)"));
+    assert!(lines[5].starts_with("5     KTJJT  KTTTT   two pair"));
+    assert_eq!("total winnings: 5905", lines[6]);
+}
@@ -33,9 +33,11 @@ impl MapDirection {
         }
     }
 
+    /*
     fn last_is_z(&self) -> bool {
         self.current.chars().last().unwrap() == 'Z'
     }
+    */
 }
 
 impl FromStr for MapDirection {
@@ -46,8 +48,8 @@ impl FromStr for MapDirection {
         let current = current.to_string();
 
         let (left, right) = rest.split_once(", ").unwrap();
-        let left = (&left[1..]).to_string();
-        let right = (&right[..right.len() - 1]).to_string();
+        let left = left[1..].to_string();
+        let right = right[..right.len() - 1].to_string();
 
         Ok(MapDirection {
             current,
@@ -60,7 +62,7 @@ impl FromStr for MapDirection {
 fn solve1(s: &str) -> usize {
     let (directions, rest) = s.split_once("\n\n").unwrap();
 
-    let directions: Vec<Direction> = directions.chars().map(|c| Direction::from(c)).collect();
+    let directions: Vec<Direction> = directions.chars().map(Direction::from).collect();
 
     let mut map_directions = HashMap::new();
     for line in rest.lines() {
@@ -83,6 +85,7 @@ fn solve1(s: &str) -> usize {
     count
 }
 
+/*
 fn solve2(s: &str) -> usize {
     let (directions, rest) = s.split_once("\n\n").unwrap();
 
@@ -122,6 +125,7 @@ fn solve2(s: &str) -> usize {
 
     count
 }
+*/
 
 pub fn answer1() {
     let input = read_input(8);
@@ -97,11 +97,7 @@ impl FromStr for History {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> anyhow::Result<Self> {
-        let nums = s
-            .trim()
-            .split_whitespace()
-            .map(|n| n.parse().unwrap())
-            .collect();
+        let nums = s.split_whitespace().map(|n| n.parse().unwrap()).collect();
 
         Ok(History { nums })
     }
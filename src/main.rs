@@ -1,3 +1,7 @@
+use std::env;
+
+use anyhow::{bail, Context};
+
 mod day01;
 mod day02;
 mod day03;
@@ -9,25 +13,71 @@ mod day08;
 mod day09;
 mod util;
 
-fn main() {
-    /*
-    day01::answer1();
-    day01::answer2();
-    day02::answer1();
-    day02::answer2();
-    day03::answer1();
-    day03::answer2();
-    day04::answer1();
-    day04::answer2();
-    day05::answer1();
-    day05::answer2();
-    day06::answer1();
-    day06::answer2();
-    day07::answer1();
-    day07::answer2();
-    day08::answer1();
-    day08::answer2();
-    */
-    day09::answer1();
-    day09::answer2();
+fn answer(day: usize) -> anyhow::Result<()> {
+    match day {
+        1 => {
+            day01::answer1();
+            day01::answer2();
+        }
+        2 => {
+            day02::answer1();
+            day02::answer2();
+        }
+        3 => {
+            day03::answer1();
+            day03::answer2();
+        }
+        4 => {
+            day04::answer1();
+            day04::answer2();
+        }
+        5 => {
+            day05::answer1();
+            day05::answer2()?;
+        }
+        6 => {
+            day06::answer1();
+            day06::answer2();
+        }
+        7 => {
+            day07::answer1();
+            day07::answer2();
+        }
+        8 => day08::answer1(),
+        9 => {
+            day09::answer1();
+            day09::answer2();
+        }
+        _ => bail!("day {day} is not solved"),
+    }
+    Ok(())
+}
+
+fn parse_day(day: &str) -> anyhow::Result<usize> {
+    day.parse().with_context(|| format!("{day} is not a day"))
+}
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.as_slice() {
+        [] => answer(9),
+        [day] => answer(parse_day(day)?),
+        [command, day, rest @ ..] => match (command.as_str(), parse_day(day)?) {
+            ("bags", 2) => day02::bags(rest),
+            ("query", 2) => day02::query(rest),
+            ("gears", 3) => day03::gears(rest),
+            ("render", 3) => day03::render(rest),
+            ("cascade", 4) => day04::cascade(rest),
+            ("score", 4) => day04::score(rest),
+            ("validate", 4) => day04::validate(rest),
+            ("convert", 5) => day05::convert(rest),
+            ("lowest", 5) => day05::lowest(rest),
+            ("boat", 6) => day06::boat(rest),
+            ("report", 6) => day06::report(rest),
+            ("rules", 7) => day07::rules(rest),
+            ("explain", 7) => day07::explain(rest),
+            (command, day) => bail!("no {command} for day {day}"),
+        },
+    }
 }
@@ -1,4 +1,4 @@
-use std::fs;
+use std::{fmt::Write, fs};
 
 pub fn read_input(day: usize) -> String {
     let path = if day > 9 {
@@ -8,7 +8,49 @@ pub fn read_input(day: usize) -> String {
     };
 
     match fs::read_to_string(&path) {
-        Ok(s) => return s,
+        Ok(s) => s,
         Err(e) => panic!("ERROR: reading file {path} was not possible. {e}"),
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+// columns as wide as their widest cell, header first
+pub fn table<const N: usize>(header: [&str; N], rows: &[[String; N]], align: Align) -> String {
+    let mut widths = header.map(str::len);
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let mut s = String::new();
+    let header = header.map(str::to_string);
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| match align {
+                Align::Left => format!("{cell:<width$}"),
+                Align::Right => format!("{cell:>width$}"),
+            })
+            .collect();
+        let _ = writeln!(s, "{}", cells.join("  ").trim_end());
+    }
+    s
+}
+
+// xorshift, good enough to make up test inputs; the seed must not be 0
+#[cfg(test)]
+pub fn xorshift(mut seed: u64) -> impl FnMut() -> u64 {
+    move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    }
+}